@@ -0,0 +1,557 @@
+use std::ops::Range;
+
+use chrono::{Days, Duration, NaiveDate, NaiveDateTime};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    models::{Journey, Model, Platform, TransportType},
+    storage::DataStorage,
+    trip::dated_stop_time,
+    utils::add_1_day,
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- BoardFilter
+// ------------------------------------------------------------------------------------------------
+
+/// Restricts the events returned by DataStorage::departures and DataStorage::arrivals.
+/// A None value means that the criterion is not used.
+#[derive(Debug, Default)]
+pub struct BoardFilter {
+    transport_type_ids: Option<FxHashSet<i32>>,
+    administrations: Option<FxHashSet<String>>,
+    lines: Option<FxHashSet<String>>,
+}
+
+impl BoardFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Getters/Setters
+
+    pub fn transport_type_ids(&self) -> Option<&FxHashSet<i32>> {
+        self.transport_type_ids.as_ref()
+    }
+
+    pub fn set_transport_type_ids(&mut self, value: Option<FxHashSet<i32>>) {
+        self.transport_type_ids = value;
+    }
+
+    pub fn administrations(&self) -> Option<&FxHashSet<String>> {
+        self.administrations.as_ref()
+    }
+
+    pub fn set_administrations(&mut self, value: Option<FxHashSet<String>>) {
+        self.administrations = value;
+    }
+
+    pub fn lines(&self) -> Option<&FxHashSet<String>> {
+        self.lines.as_ref()
+    }
+
+    pub fn set_lines(&mut self, value: Option<FxHashSet<String>>) {
+        self.lines = value;
+    }
+
+    // Functions
+
+    fn matches(&self, journey: &Journey, route_index: usize, line: Option<&str>) -> bool {
+        let transport_type_matches = self
+            .transport_type_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&journey.transport_type_id_at(route_index)));
+        let administration_matches = self
+            .administrations
+            .as_ref()
+            .is_none_or(|administrations| administrations.contains(journey.administration()));
+        let line_matches = self
+            .lines
+            .as_ref()
+            .is_none_or(|lines| line.is_some_and(|line| lines.contains(line)));
+
+        transport_type_matches && administration_matches && line_matches
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- BoardEvent
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct BoardEvent<'a> {
+    journey: &'a Journey,
+    service_date: NaiveDate,
+    stop_id: i32,
+    route_index: usize,
+    time: NaiveDateTime,
+    line: Option<&'a str>,
+    direction: Option<&'a str>,
+    transport_type: &'a TransportType,
+    check_in_minutes: i16,
+    check_out_minutes: i16,
+    is_hop_active: Vec<bool>,
+}
+
+impl<'a> BoardEvent<'a> {
+    // Getters/Setters
+
+    pub fn journey(&self) -> &'a Journey {
+        self.journey
+    }
+
+    /// The date on which the journey leaves its first stop.
+    pub fn service_date(&self) -> NaiveDate {
+        self.service_date
+    }
+
+    pub fn stop_id(&self) -> i32 {
+        self.stop_id
+    }
+
+    pub fn route_index(&self) -> usize {
+        self.route_index
+    }
+
+    /// Departure time for a departure board, arrival time for an arrival board.
+    pub fn time(&self) -> NaiveDateTime {
+        self.time
+    }
+
+    pub fn line(&self) -> Option<&'a str> {
+        self.line
+    }
+
+    /// The direction text (headsign), see Journey::direction_text_at_index.
+    pub fn direction(&self) -> Option<&'a str> {
+        self.direction
+    }
+
+    pub fn transport_type(&self) -> &'a TransportType {
+        self.transport_type
+    }
+
+    /// How long before the departure the passengers must be at the stop (*CI), e.g. for car-transport trains.
+    pub fn check_in_minutes(&self) -> i16 {
+        self.check_in_minutes
+    }
+
+    /// How long after the arrival the passengers can leave the stop (*CO).
+    pub fn check_out_minutes(&self) -> i16 {
+        self.check_out_minutes
+    }
+
+    // Functions
+
+    /// The platform is looked up on demand, see DataStorage::platform_at.
+    pub fn platform(&self, data_storage: &'a DataStorage) -> Option<&'a Platform> {
+        data_storage.platform_at(self.journey, self.route_index, self.service_date)
+    }
+
+    /// The stops served before this one, in route order. The stops of inactive sections are skipped (see DatedTrip).
+    pub fn previous_stops(&self) -> Vec<BoardStop> {
+        self.board_stops(0..self.route_index)
+    }
+
+    /// The stops served after this one, in route order. The stops of inactive sections are skipped (see DatedTrip).
+    pub fn next_stops(&self) -> Vec<BoardStop> {
+        self.board_stops(self.route_index + 1..self.journey.route().len())
+    }
+
+    /// The (journey, service date) of the trips on which the passengers can stay seated, including this one
+    /// (see DataStorage::vehicle_run). The vehicle run is resolved on demand.
+    pub fn vehicle_run(&self, data_storage: &'a DataStorage) -> Vec<(&'a Journey, NaiveDate)> {
+        data_storage.vehicle_run(self.journey, self.service_date)
+    }
+
+    fn board_stops(&self, route_indexes: Range<usize>) -> Vec<BoardStop> {
+        let route_datetimes = self.journey.route_datetimes(self.service_date);
+
+        route_indexes
+            .filter_map(|i| dated_stop_time(self.journey, &route_datetimes, &self.is_hop_active, i))
+            .map(|stop_time| BoardStop {
+                stop_id: stop_time.stop_id(),
+                arrival_at: stop_time.arrival_at(),
+                departure_at: stop_time.departure_at(),
+            })
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- BoardStop
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct BoardStop {
+    stop_id: i32,
+    arrival_at: Option<NaiveDateTime>,
+    departure_at: Option<NaiveDateTime>,
+}
+
+impl BoardStop {
+    // Getters/Setters
+
+    pub fn stop_id(&self) -> i32 {
+        self.stop_id
+    }
+
+    pub fn arrival_at(&self) -> Option<NaiveDateTime> {
+        self.arrival_at
+    }
+
+    pub fn departure_at(&self) -> Option<NaiveDateTime> {
+        self.departure_at
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq)]
+enum BoardType {
+    Departures,
+    Arrivals,
+}
+
+impl DataStorage {
+    /// Returns the departures from the stop between from (inclusive) and from + window (exclusive), sorted by departure time.
+    pub fn departures(
+        &self,
+        stop_id: i32,
+        from: NaiveDateTime,
+        window: Duration,
+        filter: &BoardFilter,
+    ) -> Vec<BoardEvent<'_>> {
+        self.board(BoardType::Departures, stop_id, from, window, filter)
+    }
+
+    /// Returns the arrivals at the stop between from (inclusive) and from + window (exclusive), sorted by arrival time.
+    pub fn arrivals(
+        &self,
+        stop_id: i32,
+        from: NaiveDateTime,
+        window: Duration,
+        filter: &BoardFilter,
+    ) -> Vec<BoardEvent<'_>> {
+        self.board(BoardType::Arrivals, stop_id, from, window, filter)
+    }
+
+    fn board(
+        &self,
+        board_type: BoardType,
+        stop_id: i32,
+        from: NaiveDateTime,
+        window: Duration,
+        filter: &BoardFilter,
+    ) -> Vec<BoardEvent<'_>> {
+        let until = from + window;
+        let Some(bit_field_ids) = self.bit_fields_by_stop_id().get(&stop_id) else {
+            return Vec::new();
+        };
+
+        // Journeys that started on a previous day can still serve the stop, possibly several days later.
+        let mut service_date = from.date() - Days::new(self.max_trip_days() as u64);
+        let mut events = Vec::new();
+
+        while service_date <= until.date() {
+            for journey in self.journeys_at_stop_on(stop_id, bit_field_ids, service_date) {
+                let route_datetimes = journey.route_datetimes(service_date);
+                let is_hop_active = self.active_hops(journey, service_date);
+
+                for (route_index, route_entry) in journey.route().iter().enumerate() {
+                    if route_entry.stop_id() != stop_id {
+                        continue;
+                    }
+
+                    // Only the active sections of the journey serve the stop (see DatedTrip).
+                    let Some(stop_time) =
+                        dated_stop_time(journey, &route_datetimes, &is_hop_active, route_index)
+                    else {
+                        continue;
+                    };
+                    // A negative time in FPLAN means that the passengers cannot board or alight there.
+                    let time = match board_type {
+                        BoardType::Departures => stop_time
+                            .departure_at()
+                            .filter(|_| route_entry.boarding_allowed()),
+                        BoardType::Arrivals => stop_time
+                            .arrival_at()
+                            .filter(|_| route_entry.alighting_allowed()),
+                    };
+                    let Some(time) = time.filter(|time| from <= *time && *time < until) else {
+                        continue;
+                    };
+
+                    let line = journey.line_designation_at(self, route_index);
+                    if !filter.matches(journey, route_index, line) {
+                        continue;
+                    }
+
+                    events.push(BoardEvent {
+                        journey,
+                        service_date,
+                        stop_id,
+                        route_index,
+                        time,
+                        line,
+                        direction: journey.direction_text_at_index(self, route_index),
+                        transport_type: journey.transport_type_at(self, route_index),
+                        check_in_minutes: journey.check_in_minutes_at(route_index),
+                        check_out_minutes: journey.check_out_minutes_at(route_index),
+                        is_hop_active: is_hop_active.clone(),
+                    });
+                }
+            }
+
+            service_date = add_1_day(service_date);
+        }
+
+        events.sort_by_key(|event| (event.time, event.journey.id(), event.route_index));
+        events
+    }
+
+    /// Returns the journeys serving the stop whose first departure is on the given date.
+    pub(crate) fn journeys_at_stop_on(
+        &self,
+        stop_id: i32,
+        bit_field_ids: &FxHashSet<i32>,
+        date: NaiveDate,
+    ) -> Vec<&Journey> {
+        let Some(active_bit_field_ids) = self.bit_fields_by_day().get(&date) else {
+            return Vec::new();
+        };
+
+        let journey_ids: FxHashSet<i32> = bit_field_ids
+            .intersection(active_bit_field_ids)
+            .filter_map(|&bit_field_id| {
+                self.journeys_by_stop_id_and_bit_field_id()
+                    .get(&(stop_id, bit_field_id))
+            })
+            .flatten()
+            .copied()
+            .collect();
+
+        journey_ids
+            .into_iter()
+            .filter_map(|journey_id| self.journeys().find(journey_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn summarize<'a>(events: &[BoardEvent<'a>]) -> Vec<(i32, NaiveDateTime, &'a str)> {
+        events
+            .iter()
+            .map(|event| {
+                (
+                    event.journey().id(),
+                    event.time(),
+                    event.transport_type().designation(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn departures() {
+        let data_storage = fixture_data_storage();
+        let events = data_storage.departures(
+            8500001,
+            datetime("2025-03-03 07:30"),
+            Duration::hours(1),
+            &BoardFilter::new(),
+        );
+
+        assert_eq!(
+            vec![
+                (1, datetime("2025-03-03 08:00"), "IC"),
+                (4, datetime("2025-03-03 08:10"), "IR"),
+            ],
+            summarize(&events)
+        );
+
+        let event = &events[0];
+        assert_eq!(Some("S1"), event.line());
+        assert_eq!(Some("Dorf"), event.direction());
+        assert_eq!(
+            Some("7"),
+            event.platform(data_storage).map(|platform| platform.name())
+        );
+        assert!(event.previous_stops().is_empty());
+        assert_eq!(
+            vec![8500002, 8500003],
+            event
+                .next_stops()
+                .iter()
+                .map(|stop| stop.stop_id())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(datetime("2025-03-03 08:30")),
+            event.next_stops()[0].arrival_at()
+        );
+    }
+
+    #[test]
+    fn arrivals() {
+        let data_storage = fixture_data_storage();
+        let events = data_storage.arrivals(
+            8500003,
+            datetime("2025-03-03 08:30"),
+            Duration::hours(1),
+            &BoardFilter::new(),
+        );
+
+        assert_eq!(
+            vec![(1, datetime("2025-03-03 09:00"), "IC")],
+            summarize(&events)
+        );
+        assert_eq!(10, events[0].check_out_minutes());
+        assert_eq!(
+            vec![8500001, 8500002],
+            events[0]
+                .previous_stops()
+                .iter()
+                .map(|stop| stop.stop_id())
+                .collect::<Vec<_>>()
+        );
+        assert!(events[0].next_stops().is_empty());
+    }
+
+    #[test]
+    fn arrivals_of_journey_started_two_days_before() {
+        let data_storage = fixture_data_storage();
+        let events = data_storage.arrivals(
+            8500005,
+            datetime("2025-03-05 00:00"),
+            Duration::hours(2),
+            &BoardFilter::new(),
+        );
+
+        assert_eq!(
+            vec![(9, datetime("2025-03-05 01:30"), "B")],
+            summarize(&events)
+        );
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            events[0].service_date()
+        );
+    }
+
+    #[test]
+    fn filter_by_transport_type_at_route_index() {
+        let data_storage = fixture_data_storage();
+        let ir_id = data_storage.transport_types_by_designation()["IR"];
+        let mut filter = BoardFilter::new();
+        filter.set_transport_type_ids(Some(FxHashSet::from_iter([ir_id])));
+
+        // Journey 10 is a bus from Alpha to Beta, then an IR from Beta to Gamma.
+        let departures = data_storage.departures(
+            8500001,
+            datetime("2025-03-03 12:30"),
+            Duration::hours(1),
+            &filter,
+        );
+        assert!(departures.is_empty());
+
+        let arrivals = data_storage.arrivals(
+            8500003,
+            datetime("2025-03-03 13:00"),
+            Duration::hours(1),
+            &filter,
+        );
+        assert_eq!(
+            vec![(10, datetime("2025-03-03 13:30"), "IR")],
+            summarize(&arrivals)
+        );
+    }
+
+    #[test]
+    fn vehicle_run_is_resolved_on_demand() {
+        let data_storage = fixture_data_storage();
+        let events = data_storage.departures(
+            8500001,
+            datetime("2025-03-03 07:00"),
+            Duration::minutes(1),
+            &BoardFilter::new(),
+        );
+
+        assert_eq!(1, events.len());
+        assert_eq!(
+            vec![6, 7],
+            events[0]
+                .vehicle_run(data_storage)
+                .iter()
+                .map(|(journey, _)| journey.id())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn no_boarding_or_alighting_where_the_time_is_negative() {
+        let data_storage = fixture_data_storage();
+        let from = datetime("2025-03-03 12:00");
+
+        // The journey 8 serves Beta at 12:10/12:11 without letting the passengers board or alight.
+        let events =
+            data_storage.departures(8500002, from, Duration::hours(2), &BoardFilter::new());
+        assert_eq!(
+            vec![
+                (9, datetime("2025-03-03 12:10"), "B"),
+                (10, datetime("2025-03-03 13:12"), "B"),
+            ],
+            summarize(&events)
+        );
+        let events = data_storage.arrivals(8500002, from, Duration::hours(2), &BoardFilter::new());
+        assert_eq!(
+            vec![
+                (9, datetime("2025-03-03 12:00"), "B"),
+                (10, datetime("2025-03-03 13:10"), "B"),
+            ],
+            summarize(&events)
+        );
+    }
+
+    #[test]
+    fn inactive_sections_are_not_served() {
+        let data_storage = fixture_data_storage();
+        let stop_ids =
+            |stops: Vec<BoardStop>| stops.iter().map(|stop| stop.stop_id()).collect::<Vec<_>>();
+
+        // The section of the journey 8 from Gamma to Delta only runs on the first two days of the timetable.
+        let from = datetime("2025-03-03 12:00");
+        let events =
+            data_storage.departures(8500003, from, Duration::hours(2), &BoardFilter::new());
+        assert!(events.is_empty());
+        let events = data_storage.arrivals(8500004, from, Duration::hours(2), &BoardFilter::new());
+        assert!(events.is_empty());
+        let events =
+            data_storage.departures(8500001, from, Duration::minutes(30), &BoardFilter::new());
+        assert_eq!(
+            vec![(8, datetime("2025-03-03 12:00"), "B")],
+            summarize(&events)
+        );
+        assert_eq!(vec![8500002], stop_ids(events[0].next_stops()));
+
+        let from = datetime("2025-01-01 12:00");
+        let events =
+            data_storage.departures(8500003, from, Duration::hours(2), &BoardFilter::new());
+        assert_eq!(
+            vec![(8, datetime("2025-01-01 12:21"), "B")],
+            summarize(&events)
+        );
+        assert_eq!(vec![8500001, 8500002], stop_ids(events[0].previous_stops()));
+        assert_eq!(vec![8500004], stop_ids(events[0].next_stops()));
+    }
+}
@@ -0,0 +1,1110 @@
+// The days are stored as a bitset in 64-bit words: the day i of the period is the bit i % 64 of the word i / 64.
+// The bits beyond the last day of the period are always 0.
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{BitField, Holiday, Language},
+    storage::DataStorage,
+    utils::{count_days_between_two_dates, timetable_end_date, timetable_start_date},
+};
+
+// The first two bits of a BITFELD entry do not correspond to any day.
+const BIT_FIELD_OFFSET: usize = 2;
+// The weekdays are stored as a bitset, Monday being the bit 0.
+const ALL_WEEKDAYS: u8 = 0b111_1111;
+
+// Beyond this number of items (weekday groups, date ranges, etc.), the service is described as irregular.
+const MAX_DESCRIPTION_ITEMS: usize = 20;
+
+// The first and last days (inclusive) of a range of days or weekdays.
+type DayRange = (usize, usize);
+
+// ------------------------------------------------------------------------------------------------
+// --- ServiceCalendar
+// ------------------------------------------------------------------------------------------------
+
+/// The days on which a service runs, within a period starting on start_date and lasting day_count days
+/// (usually the timetable period, see ECKDATEN).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ServiceCalendar {
+    start_date: NaiveDate,
+    day_count: usize,
+    words: Vec<u64>,
+}
+
+impl ServiceCalendar {
+    /// Returns a calendar without any day.
+    pub fn new(start_date: NaiveDate, day_count: usize) -> Self {
+        Self {
+            start_date,
+            day_count,
+            words: vec![0; day_count.div_ceil(64)],
+        }
+    }
+
+    /// Returns a calendar with every day of the period.
+    pub fn full(start_date: NaiveDate, day_count: usize) -> Self {
+        let mut calendar = Self::new(start_date, day_count);
+        calendar.words.fill(u64::MAX);
+        calendar.clear_unused_bits();
+        calendar
+    }
+
+    /// Returns the calendar of the bit field. The first two bits of the bit field are skipped,
+    /// the third one being the start date of the period.
+    pub fn from_bit_field(bit_field: &BitField, start_date: NaiveDate, day_count: usize) -> Self {
+        let mut calendar = Self::new(start_date, day_count);
+        for day in 0..day_count {
+            if bit_field.bit(day + BIT_FIELD_OFFSET) {
+                calendar.words[day / 64] |= 1 << (day % 64);
+            }
+        }
+        calendar
+    }
+
+    // Getters/Setters
+
+    pub fn start_date(&self) -> NaiveDate {
+        self.start_date
+    }
+
+    /// The last day of the period (inclusive).
+    pub fn end_date(&self) -> NaiveDate {
+        self.date_of(self.day_count.saturating_sub(1))
+    }
+
+    /// The number of days of the period.
+    pub fn day_count(&self) -> usize {
+        self.day_count
+    }
+
+    // Functions
+
+    pub fn runs_on(&self, date: NaiveDate) -> bool {
+        self.day_of(date).is_some_and(|day| self.runs_on_day(day))
+    }
+
+    /// Adds the date to the calendar. Returns false if the date is outside of the period.
+    pub fn insert(&mut self, date: NaiveDate) -> bool {
+        let Some(day) = self.day_of(date) else {
+            return false;
+        };
+        self.words[day / 64] |= 1 << (day % 64);
+        true
+    }
+
+    /// Removes the date from the calendar. Returns false if the date is outside of the period.
+    pub fn remove(&mut self, date: NaiveDate) -> bool {
+        let Some(day) = self.day_of(date) else {
+            return false;
+        };
+        self.words[day / 64] &= !(1 << (day % 64));
+        true
+    }
+
+    /// Returns the dates on which the service runs, in chronological order.
+    pub fn dates(&self) -> Vec<NaiveDate> {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &word)| {
+                (0..64)
+                    .filter(move |bit| (word >> bit) & 1 == 1)
+                    .map(move |bit| i * 64 + bit)
+            })
+            .map(|day| self.date_of(day))
+            .collect()
+    }
+
+    /// Returns the number of days on which the service runs.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Returns the days on which self or other runs, within the period of self.
+    pub fn union(&self, other: &ServiceCalendar) -> ServiceCalendar {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Returns the days on which self and other run, within the period of self.
+    pub fn intersection(&self, other: &ServiceCalendar) -> ServiceCalendar {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Returns the days on which self runs but not other, within the period of self.
+    pub fn difference(&self, other: &ServiceCalendar) -> ServiceCalendar {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    fn combine<F>(&self, other: &ServiceCalendar, operation: F) -> ServiceCalendar
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        let other = other.aligned_to(self);
+        let mut calendar = self.clone();
+        for (word, &other_word) in calendar.words.iter_mut().zip(&other.words) {
+            *word = operation(*word, other_word);
+        }
+        calendar.clear_unused_bits();
+        calendar
+    }
+
+    /// Returns the calendar with the period of other. The days outside of the period of other are dropped.
+    fn aligned_to(&self, other: &ServiceCalendar) -> ServiceCalendar {
+        if self.start_date == other.start_date && self.day_count == other.day_count {
+            return self.clone();
+        }
+
+        let mut calendar = ServiceCalendar::new(other.start_date, other.day_count);
+        for date in self.dates() {
+            calendar.insert(date);
+        }
+        calendar
+    }
+
+    /// Returns a concise description of the days on which the service runs, e.g. "Mon–Fri, not 25 Dec, 1 Jan".
+    /// The description is made of an optional validity range, the weekdays (with or without the holidays)
+    /// and the dates on which the service does not run or also runs. The shortest description is chosen.
+    /// The service is described as irregular if the description would be too long.
+    pub fn describe(&self, holidays: &[&Holiday], language: Language) -> String {
+        let vocabulary = Vocabulary::of(language);
+        let running: Vec<bool> = (0..self.day_count)
+            .map(|day| self.runs_on_day(day))
+            .collect();
+
+        let (Some(first_day), Some(last_day)) = (
+            running.iter().position(|&runs| runs),
+            running.iter().rposition(|&runs| runs),
+        ) else {
+            return vocabulary.never.to_owned();
+        };
+
+        let mut is_holiday = vec![false; self.day_count];
+        for holiday in holidays {
+            if let Some(day) = self.day_of(holiday.date()) {
+                is_holiday[day] = true;
+            }
+        }
+
+        let first_weekday = self.start_date.weekday().num_days_from_monday() as usize;
+        let full_period = (0, self.day_count - 1);
+        let mut windows = vec![full_period];
+        if (first_day, last_day) != full_period {
+            windows.push((first_day, last_day));
+        }
+
+        let mut best: Option<OperatingDays> = None;
+        for &window in &windows {
+            for holiday_mode in [
+                HolidayMode::Ignored,
+                HolidayMode::Excluded,
+                HolidayMode::Included,
+            ] {
+                // A list of dates first, then from every weekday down to a single weekday.
+                for weekdays in std::iter::once(0).chain((1..=ALL_WEEKDAYS).rev()) {
+                    if weekdays == 0
+                        && (window != full_period || holiday_mode != HolidayMode::Ignored)
+                    {
+                        continue;
+                    }
+
+                    let expected = |day: usize| {
+                        let weekday = (first_weekday + day) % 7;
+                        let runs_on_weekday = (weekdays >> weekday) & 1 == 1;
+                        match holiday_mode {
+                            HolidayMode::Ignored => runs_on_weekday,
+                            HolidayMode::Excluded => runs_on_weekday && !is_holiday[day],
+                            HolidayMode::Included => runs_on_weekday || is_holiday[day],
+                        }
+                    };
+                    let (not_running, also_running) = deviations(&running, window, expected);
+
+                    let candidate = OperatingDays {
+                        window: (window != full_period).then_some(window),
+                        weekdays,
+                        holiday_mode,
+                        not_running,
+                        also_running,
+                    };
+                    if best
+                        .as_ref()
+                        .is_none_or(|best| candidate.cost() < best.cost())
+                    {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+
+        // unwrap: There is always at least one candidate.
+        let best = best.unwrap();
+        if best.cost() > MAX_DESCRIPTION_ITEMS {
+            return vocabulary.irregular.to_owned();
+        }
+        best.describe(self, vocabulary)
+    }
+
+    fn runs_on_day(&self, day: usize) -> bool {
+        (self.words[day / 64] >> (day % 64)) & 1 == 1
+    }
+
+    fn day_of(&self, date: NaiveDate) -> Option<usize> {
+        let day = usize::try_from((date - self.start_date).num_days()).ok()?;
+        (day < self.day_count).then_some(day)
+    }
+
+    fn date_of(&self, day: usize) -> NaiveDate {
+        self.start_date
+            // unwrap: Converting day from usize to u64 will never fail.
+            .checked_add_days(Days::new(day.try_into().unwrap()))
+            // unwrap: Adding days will never fail.
+            .unwrap()
+    }
+
+    /// Returns true if the same day of another year is also in the period, i.e. if the year must be shown.
+    fn is_ambiguous(&self, date: NaiveDate) -> bool {
+        date.checked_sub_months(Months::new(12))
+            .is_some_and(|date| date >= self.start_date)
+            || date
+                .checked_add_months(Months::new(12))
+                .is_some_and(|date| date <= self.end_date())
+    }
+
+    fn format_date(&self, date: NaiveDate, vocabulary: &Vocabulary, with_year: bool) -> String {
+        let mut text = format!(
+            "{}{} {}",
+            date.day(),
+            vocabulary.day_suffix,
+            vocabulary.months[date.month0() as usize]
+        );
+        if with_year {
+            text.push_str(&format!(" {}", date.year()));
+        }
+        text
+    }
+
+    /// Formats a range of days, e.g. "25 Dec" or "7–18 Jul".
+    fn format_range(&self, (first_day, last_day): DayRange, vocabulary: &Vocabulary) -> String {
+        let (first_date, last_date) = (self.date_of(first_day), self.date_of(last_day));
+        let with_year = self.is_ambiguous(first_date) || self.is_ambiguous(last_date);
+        let last = self.format_date(last_date, vocabulary, with_year);
+
+        if first_day == last_day {
+            last
+        } else if (first_date.year(), first_date.month()) == (last_date.year(), last_date.month()) {
+            format!("{}{}–{}", first_date.day(), vocabulary.day_suffix, last)
+        } else {
+            format!(
+                "{}–{}",
+                self.format_date(first_date, vocabulary, with_year),
+                last
+            )
+        }
+    }
+
+    fn clear_unused_bits(&mut self) {
+        if let Some(last_word) = self.words.last_mut()
+            && !self.day_count.is_multiple_of(64)
+        {
+            *last_word &= (1 << (self.day_count % 64)) - 1;
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DayType
+// ------------------------------------------------------------------------------------------------
+
+/// The type of a date, as used in timetables. A holiday falling on a weekend is a holiday.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum DayType {
+    Weekday, // From Monday to Friday.
+    Saturday,
+    Sunday,
+    Holiday,
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- OperatingDays
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum HolidayMode {
+    Ignored,
+    Excluded,
+    Included,
+}
+
+/// A candidate description of a calendar. The days are relative to the start date of the calendar.
+struct OperatingDays {
+    window: Option<DayRange>, // A None value means that the description applies to the whole period.
+    weekdays: u8, // A value of 0 means that the service only runs on the also_running days.
+    holiday_mode: HolidayMode,
+    not_running: Vec<DayRange>,
+    also_running: Vec<DayRange>,
+}
+
+impl OperatingDays {
+    /// The number of items of the description.
+    fn cost(&self) -> usize {
+        let weekdays_cost = match self.weekdays {
+            0 | ALL_WEEKDAYS => 0,
+            weekdays => weekday_groups(weekdays)
+                .len()
+                .min(weekday_groups(!weekdays & ALL_WEEKDAYS).len()),
+        };
+
+        weekdays_cost
+            + usize::from(self.window.is_some())
+            + usize::from(self.holiday_mode != HolidayMode::Ignored)
+            + self.not_running.len()
+            + self.also_running.len()
+    }
+
+    fn describe(&self, calendar: &ServiceCalendar, vocabulary: &Vocabulary) -> String {
+        let format_ranges = |ranges: &[DayRange]| {
+            ranges
+                .iter()
+                .map(|&range| calendar.format_range(range, vocabulary))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        if self.weekdays == 0 {
+            return format!("{}{}", vocabulary.only, format_ranges(&self.also_running));
+        }
+
+        let mut parts = Vec::new();
+
+        if let Some((first_day, last_day)) = self.window {
+            parts.push(format!(
+                "{}{}{}{}",
+                vocabulary.from,
+                calendar.format_range((first_day, first_day), vocabulary),
+                vocabulary.to,
+                calendar.format_range((last_day, last_day), vocabulary)
+            ));
+        }
+
+        let holidays = match self.holiday_mode {
+            HolidayMode::Ignored => "",
+            HolidayMode::Excluded => vocabulary.except_holidays,
+            HolidayMode::Included => vocabulary.and_holidays,
+        };
+        parts.push(format!(
+            "{}{}",
+            describe_weekdays(self.weekdays, vocabulary),
+            holidays
+        ));
+
+        if !self.not_running.is_empty() {
+            parts.push(format!(
+                "{}{}",
+                vocabulary.not,
+                format_ranges(&self.not_running)
+            ));
+        }
+        if !self.also_running.is_empty() {
+            parts.push(format!(
+                "{}{}",
+                vocabulary.also,
+                format_ranges(&self.also_running)
+            ));
+        }
+
+        parts.join(", ")
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Vocabulary
+// ------------------------------------------------------------------------------------------------
+
+struct Vocabulary {
+    daily: &'static str,
+    daily_except: &'static str,
+    never: &'static str,
+    irregular: &'static str,
+    not: &'static str,
+    also: &'static str,
+    only: &'static str,
+    except_holidays: &'static str,
+    and_holidays: &'static str,
+    from: &'static str,
+    to: &'static str,
+    day_suffix: &'static str,
+    weekdays: [&'static str; 7],
+    months: [&'static str; 12],
+}
+
+impl Vocabulary {
+    fn of(language: Language) -> &'static Vocabulary {
+        match language {
+            Language::German => &GERMAN,
+            Language::French => &FRENCH,
+            Language::Italian => &ITALIAN,
+            Language::English => &ENGLISH,
+        }
+    }
+}
+
+static GERMAN: Vocabulary = Vocabulary {
+    daily: "täglich",
+    daily_except: "täglich ausser ",
+    never: "verkehrt nicht",
+    irregular: "unregelmässig",
+    not: "nicht ",
+    also: "auch ",
+    only: "nur ",
+    except_holidays: " ausser allg. Feiertage",
+    and_holidays: " und allg. Feiertage",
+    from: "vom ",
+    to: " bis ",
+    day_suffix: ".",
+    weekdays: ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    months: [
+        "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.",
+        "Dez.",
+    ],
+};
+
+static FRENCH: Vocabulary = Vocabulary {
+    daily: "tous les jours",
+    daily_except: "tous les jours sauf ",
+    never: "ne circule pas",
+    irregular: "irrégulier",
+    not: "pas le ",
+    also: "aussi le ",
+    only: "seulement le ",
+    except_holidays: " sauf jours fériés",
+    and_holidays: " et jours fériés",
+    from: "du ",
+    to: " au ",
+    day_suffix: "",
+    weekdays: ["lu", "ma", "me", "je", "ve", "sa", "di"],
+    months: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.",
+        "déc.",
+    ],
+};
+
+static ITALIAN: Vocabulary = Vocabulary {
+    daily: "giornaliero",
+    daily_except: "giornaliero eccetto ",
+    never: "non circola",
+    irregular: "irregolare",
+    not: "non il ",
+    also: "anche il ",
+    only: "solo il ",
+    except_holidays: " esclusi i giorni festivi",
+    and_holidays: " e giorni festivi",
+    from: "dal ",
+    to: " al ",
+    day_suffix: "",
+    weekdays: ["lu", "ma", "me", "gi", "ve", "sa", "do"],
+    months: [
+        "gen.", "feb.", "mar.", "apr.", "mag.", "giu.", "lug.", "ago.", "set.", "ott.", "nov.",
+        "dic.",
+    ],
+};
+
+static ENGLISH: Vocabulary = Vocabulary {
+    daily: "daily",
+    daily_except: "daily except ",
+    never: "does not run",
+    irregular: "irregular",
+    not: "not ",
+    also: "also ",
+    only: "only ",
+    except_holidays: " except public holidays",
+    and_holidays: " and public holidays",
+    from: "from ",
+    to: " to ",
+    day_suffix: "",
+    weekdays: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    months: [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ],
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the calendar of the bit field over the timetable period.
+    /// A None value or a value of 0 means that the service runs every day of the timetable period.
+    /// An unknown bit field results in an empty calendar.
+    pub fn service_calendar(&self, bit_field_id: Option<i32>) -> ServiceCalendar {
+        let (start_date, day_count) = self.timetable_period();
+
+        match bit_field_id {
+            None | Some(0) => ServiceCalendar::full(start_date, day_count),
+            Some(id) => match self.bit_fields().find(id) {
+                Some(bit_field) => {
+                    ServiceCalendar::from_bit_field(bit_field, start_date, day_count)
+                }
+                None => ServiceCalendar::new(start_date, day_count),
+            },
+        }
+    }
+
+    /// Returns the description of the days on which the service of the bit field runs, taking
+    /// the holidays into account (see ServiceCalendar::describe).
+    pub fn operating_days_description(
+        &self,
+        bit_field_id: Option<i32>,
+        language: Language,
+    ) -> String {
+        self.service_calendar(bit_field_id)
+            .describe(&self.holidays().entries(), language)
+    }
+
+    pub fn holiday(&self, date: NaiveDate) -> Option<&Holiday> {
+        self.holidays_by_date()
+            .get(&date)
+            .and_then(|&id| self.holidays().find(id))
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays_by_date().contains_key(&date)
+    }
+
+    pub fn holiday_name(&self, date: NaiveDate, language: Language) -> Option<&str> {
+        self.holiday(date)?.name(language)
+    }
+
+    pub fn day_type(&self, date: NaiveDate) -> DayType {
+        if self.is_holiday(date) {
+            return DayType::Holiday;
+        }
+
+        match date.weekday() {
+            Weekday::Sat => DayType::Saturday,
+            Weekday::Sun => DayType::Sunday,
+            _ => DayType::Weekday,
+        }
+    }
+
+    /// Returns the number of days of each type on which the service runs.
+    /// The types on which the service never runs are omitted.
+    pub fn count_by_day_type(&self, calendar: &ServiceCalendar) -> FxHashMap<DayType, usize> {
+        calendar
+            .dates()
+            .into_iter()
+            .fold(FxHashMap::default(), |mut acc, date| {
+                *acc.entry(self.day_type(date)).or_default() += 1;
+                acc
+            })
+    }
+
+    /// Returns the (start date, number of days) of the timetable period.
+    pub(crate) fn timetable_period(&self) -> (NaiveDate, usize) {
+        // unwrap: The timetable period is checked when the data storage is built.
+        let start_date = timetable_start_date(self.timetable_metadata()).unwrap();
+        let end_date = timetable_end_date(self.timetable_metadata()).unwrap();
+        (
+            start_date,
+            count_days_between_two_dates(start_date, end_date),
+        )
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Returns the ranges of days on which the service does not run although expected, and the ranges of days
+/// on which it runs although not expected. A range is only interrupted by a day contradicting it,
+/// e.g. the weekends do not interrupt a range of weekdays on which the service does not run.
+fn deviations<F>(
+    running: &[bool],
+    (first_day, last_day): DayRange,
+    expected: F,
+) -> (Vec<DayRange>, Vec<DayRange>)
+where
+    F: Fn(usize) -> bool,
+{
+    let mut not_running = Vec::new();
+    let mut also_running = Vec::new();
+    let mut not_running_open = false;
+    let mut also_running_open = false;
+
+    for (day, &runs) in running
+        .iter()
+        .enumerate()
+        .take(last_day + 1)
+        .skip(first_day)
+    {
+        if runs {
+            not_running_open = false;
+            if !expected(day) {
+                extend_ranges(&mut also_running, &mut also_running_open, day);
+            }
+        } else {
+            also_running_open = false;
+            if expected(day) {
+                extend_ranges(&mut not_running, &mut not_running_open, day);
+            }
+        }
+    }
+
+    (not_running, also_running)
+}
+
+fn extend_ranges(ranges: &mut Vec<DayRange>, open: &mut bool, day: usize) {
+    match ranges.last_mut() {
+        Some(range) if *open => range.1 = day,
+        _ => ranges.push((day, day)),
+    }
+    *open = true;
+}
+
+/// Returns the ranges of consecutive weekdays, Monday being 0.
+fn weekday_groups(weekdays: u8) -> Vec<DayRange> {
+    let mut groups: Vec<DayRange> = Vec::new();
+    for weekday in (0..7).filter(|weekday| (weekdays >> weekday) & 1 == 1) {
+        match groups.last_mut() {
+            Some(group) if group.1 + 1 == weekday => group.1 = weekday,
+            _ => groups.push((weekday, weekday)),
+        }
+    }
+    groups
+}
+
+/// Describes the weekdays, e.g. "Mon–Fri", "Sat, Sun" or "daily except Wed".
+fn describe_weekdays(weekdays: u8, vocabulary: &Vocabulary) -> String {
+    if weekdays == ALL_WEEKDAYS {
+        return vocabulary.daily.to_owned();
+    }
+
+    let format_groups = |groups: Vec<DayRange>| {
+        groups
+            .into_iter()
+            .map(|(first, last)| match last - first {
+                0 => vocabulary.weekdays[first].to_owned(),
+                1 => format!(
+                    "{}, {}",
+                    vocabulary.weekdays[first], vocabulary.weekdays[last]
+                ),
+                _ => format!(
+                    "{}–{}",
+                    vocabulary.weekdays[first], vocabulary.weekdays[last]
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let groups = weekday_groups(weekdays);
+    let missing_groups = weekday_groups(!weekdays & ALL_WEEKDAYS);
+    if missing_groups.len() < groups.len() {
+        format!(
+            "{}{}",
+            vocabulary.daily_except,
+            format_groups(missing_groups)
+        )
+    } else {
+        format_groups(groups)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// A calendar from Monday 1 Dec 2025 to Saturday 31 Jan 2026, running on the dates accepted by runs.
+    fn calendar(runs: impl Fn(NaiveDate) -> bool) -> ServiceCalendar {
+        let mut calendar = ServiceCalendar::new(date(2025, 12, 1), 62);
+        for date in ServiceCalendar::full(date(2025, 12, 1), 62).dates() {
+            if runs(date) {
+                calendar.insert(date);
+            }
+        }
+        calendar
+    }
+
+    fn weekdays_except_christmas_and_new_year() -> ServiceCalendar {
+        calendar(|date| {
+            date.weekday().num_days_from_monday() < 5
+                && date != self::date(2025, 12, 25)
+                && date != self::date(2026, 1, 1)
+        })
+    }
+
+    fn holidays(dates: &[NaiveDate]) -> Vec<Holiday> {
+        dates
+            .iter()
+            .enumerate()
+            .map(|(i, &date)| Holiday::new(i as i32 + 1, date, FxHashMap::default()))
+            .collect()
+    }
+
+    fn calendar_of(
+        start_date: NaiveDate,
+        day_count: usize,
+        dates: &[NaiveDate],
+    ) -> ServiceCalendar {
+        let mut calendar = ServiceCalendar::new(start_date, day_count);
+        for &date in dates {
+            assert!(calendar.insert(date));
+        }
+        calendar
+    }
+
+    #[test]
+    fn set_operations_across_periods() {
+        // From 1 to 10 Jan 2025, and from 30 Dec 2024 to 5 Jan 2025.
+        let a = calendar_of(
+            date(2025, 1, 1),
+            10,
+            &[date(2025, 1, 2), date(2025, 1, 3), date(2025, 1, 4)],
+        );
+        let b = calendar_of(
+            date(2024, 12, 30),
+            7,
+            &[
+                date(2024, 12, 30),
+                date(2025, 1, 3),
+                date(2025, 1, 4),
+                date(2025, 1, 5),
+            ],
+        );
+
+        // The result has the period of self, 30 Dec is dropped.
+        let union = a.union(&b);
+        assert_eq!(
+            (date(2025, 1, 1), 10),
+            (union.start_date(), union.day_count())
+        );
+        assert_eq!(
+            vec![
+                date(2025, 1, 2),
+                date(2025, 1, 3),
+                date(2025, 1, 4),
+                date(2025, 1, 5)
+            ],
+            union.dates()
+        );
+        assert_eq!(
+            vec![date(2025, 1, 3), date(2025, 1, 4)],
+            a.intersection(&b).dates()
+        );
+        assert_eq!(vec![date(2025, 1, 2)], a.difference(&b).dates());
+
+        // The other way round, the days of a after 5 Jan are dropped.
+        let union = b.union(&a);
+        assert_eq!(
+            (date(2024, 12, 30), date(2025, 1, 5)),
+            (union.start_date(), union.end_date())
+        );
+        assert_eq!(
+            vec![
+                date(2024, 12, 30),
+                date(2025, 1, 2),
+                date(2025, 1, 3),
+                date(2025, 1, 4),
+                date(2025, 1, 5)
+            ],
+            union.dates()
+        );
+        assert_eq!(
+            vec![date(2024, 12, 30), date(2025, 1, 5)],
+            b.difference(&a).dates()
+        );
+    }
+
+    #[test]
+    fn unused_bits_at_word_boundary() {
+        let start_date = date(2025, 1, 1);
+
+        let calendar = ServiceCalendar::full(start_date, 64);
+        assert_eq!(vec![u64::MAX], calendar.words);
+        assert_eq!(64, calendar.count());
+        assert_eq!(date(2025, 3, 5), calendar.end_date());
+
+        let calendar = ServiceCalendar::full(start_date, 65);
+        assert_eq!(vec![u64::MAX, 1], calendar.words);
+        assert_eq!(65, calendar.count());
+
+        let calendar = ServiceCalendar::full(start_date, 63);
+        assert_eq!(vec![u64::MAX >> 1], calendar.words);
+
+        // The days beyond the period of self do not leak into the last word.
+        let longer = ServiceCalendar::full(start_date, 128);
+        let union = ServiceCalendar::new(start_date, 65).union(&longer);
+        assert_eq!(vec![u64::MAX, 1], union.words);
+        assert_eq!(union, ServiceCalendar::full(start_date, 65));
+        assert!(
+            ServiceCalendar::full(start_date, 65)
+                .difference(&longer)
+                .is_empty()
+        );
+
+        let mut calendar = ServiceCalendar::new(start_date, 65);
+        assert!(calendar.insert(date(2025, 3, 6)));
+        assert!(!calendar.insert(date(2025, 3, 7)));
+        assert_eq!(vec![0, 1], calendar.words);
+        assert!(calendar.remove(date(2025, 3, 6)));
+        assert!(calendar.is_empty());
+    }
+
+    #[test]
+    fn from_bit_field() {
+        let start_date = date(2025, 1, 1);
+
+        // The first two bits are skipped, the third one is 1 Jan.
+        let bit_field = BitField::new(1, vec![1, 1, 1, 0, 1]);
+        assert_eq!(
+            vec![date(2025, 1, 1), date(2025, 1, 3)],
+            ServiceCalendar::from_bit_field(&bit_field, start_date, 10).dates()
+        );
+        let bit_field = BitField::new(2, vec![1, 1, 0, 0]);
+        assert!(ServiceCalendar::from_bit_field(&bit_field, start_date, 10).is_empty());
+        // The bits beyond the period are ignored.
+        let bit_field = BitField::new(3, vec![0, 0, 1, 1, 1, 1]);
+        assert_eq!(
+            vec![date(2025, 1, 1), date(2025, 1, 2)],
+            ServiceCalendar::from_bit_field(&bit_field, start_date, 2).dates()
+        );
+    }
+
+    #[test]
+    fn service_calendar() {
+        let data_storage = fixture_data_storage();
+
+        // The bit field 3 ("3000…") runs on the first two days of the timetable.
+        assert_eq!(
+            vec![date(2025, 1, 1), date(2025, 1, 2)],
+            data_storage.service_calendar(Some(3)).dates()
+        );
+        // The bit field 4 ("2888…") runs on 1 Jan, then every 4 days from 3 Jan.
+        assert_eq!(
+            vec![
+                date(2025, 1, 1),
+                date(2025, 1, 3),
+                date(2025, 1, 7),
+                date(2025, 1, 11)
+            ],
+            data_storage.service_calendar(Some(4)).dates()[..4]
+        );
+        assert!(data_storage.service_calendar(Some(2)).is_empty());
+        assert!(data_storage.service_calendar(Some(99)).is_empty());
+        assert_eq!(365, data_storage.service_calendar(None).count());
+    }
+
+    #[test]
+    fn operating_dates() {
+        let data_storage = fixture_data_storage();
+        let calendar = data_storage
+            .journeys()
+            .find(1)
+            .unwrap()
+            .operating_dates(data_storage);
+
+        assert_eq!(
+            (date(2025, 1, 1), date(2025, 12, 31)),
+            (calendar.start_date(), calendar.end_date())
+        );
+        assert_eq!(365, calendar.count());
+        assert!(calendar.runs_on(date(2025, 12, 31)));
+        assert!(!calendar.runs_on(date(2026, 1, 1)));
+        assert!(!calendar.runs_on(date(2024, 12, 31)));
+    }
+
+    #[test]
+    fn describe_weekdays_with_exceptions() {
+        let calendar = weekdays_except_christmas_and_new_year();
+        assert_eq!(
+            "Mon–Fri, not 25 Dec, 1 Jan",
+            calendar.describe(&[], Language::English)
+        );
+        assert_eq!(
+            "Mo–Fr, nicht 25. Dez., 1. Jan.",
+            calendar.describe(&[], Language::German)
+        );
+        assert_eq!(
+            "lu–ve, non il 25 dic., 1 gen.",
+            calendar.describe(&[], Language::Italian)
+        );
+    }
+
+    #[test]
+    fn describe_daily_except_a_weekday() {
+        let calendar = calendar(|date| date.weekday() != Weekday::Sat);
+        assert_eq!(
+            "daily except Sat",
+            calendar.describe(&[], Language::English)
+        );
+        assert_eq!(
+            "täglich ausser Sa",
+            calendar.describe(&[], Language::German)
+        );
+    }
+
+    #[test]
+    fn describe_in_french() {
+        let calendar = weekdays_except_christmas_and_new_year();
+        assert_eq!(
+            "lu–ve, pas le 25 déc., 1 janv.",
+            calendar.describe(&[], Language::French)
+        );
+        let holidays = holidays(&[date(2025, 12, 25), date(2026, 1, 1)]);
+        let holidays: Vec<&Holiday> = holidays.iter().collect();
+        assert_eq!(
+            "lu–ve sauf jours fériés",
+            calendar.describe(&holidays, Language::French)
+        );
+    }
+
+    #[test]
+    fn describe_including_holidays() {
+        // 27 Dec and 3 Jan are Saturdays.
+        let holidays = holidays(&[date(2025, 12, 27), date(2026, 1, 3)]);
+        let holidays: Vec<&Holiday> = holidays.iter().collect();
+        let calendar = calendar(|date| {
+            date.weekday().num_days_from_monday() < 5
+                || holidays.iter().any(|holiday| holiday.date() == date)
+        });
+
+        assert_eq!(
+            "Mon–Fri, also 27 Dec, 3 Jan",
+            calendar.describe(&[], Language::English)
+        );
+        assert_eq!(
+            "Mon–Fri and public holidays",
+            calendar.describe(&holidays, Language::English)
+        );
+        assert_eq!(
+            "lu–ve e giorni festivi",
+            calendar.describe(&holidays, Language::Italian)
+        );
+    }
+
+    #[test]
+    fn describe_validity_window() {
+        let calendar = calendar(|date| {
+            date.weekday().num_days_from_monday() < 5
+                && date >= self::date(2025, 12, 10)
+                && date <= self::date(2026, 1, 20)
+        });
+        assert_eq!(
+            "from 10 Dec to 20 Jan, Mon–Fri",
+            calendar.describe(&[], Language::English)
+        );
+        assert_eq!(
+            "vom 10. Dez. bis 20. Jan., Mo–Fr",
+            calendar.describe(&[], Language::German)
+        );
+    }
+
+    #[test]
+    fn describe_only_dates() {
+        let dates = [date(2025, 12, 2), date(2025, 12, 17), date(2026, 1, 9)];
+        let calendar = calendar(|date| dates.contains(&date));
+        assert_eq!(
+            "only 2 Dec, 17 Dec, 9 Jan",
+            calendar.describe(&[], Language::English)
+        );
+        assert_eq!(
+            "seulement le 2 déc., 17 déc., 9 janv.",
+            calendar.describe(&[], Language::French)
+        );
+    }
+
+    #[test]
+    fn describe_irregular() {
+        // Every other day: any description would need more than MAX_DESCRIPTION_ITEMS items.
+        let calendar = calendar(|date| (date - self::date(2025, 12, 1)).num_days() % 2 == 0);
+        assert_eq!("irregular", calendar.describe(&[], Language::English));
+        assert_eq!("irregolare", calendar.describe(&[], Language::Italian));
+    }
+
+    #[test]
+    fn describe_never() {
+        let calendar = calendar(|_| false);
+        assert_eq!("verkehrt nicht", calendar.describe(&[], Language::German));
+        assert_eq!("does not run", calendar.describe(&[], Language::English));
+    }
+
+    #[test]
+    fn holidays_of_the_data_storage() {
+        let data_storage = fixture_data_storage();
+
+        assert_eq!(
+            Some(date(2025, 12, 25)),
+            data_storage
+                .holiday(date(2025, 12, 25))
+                .map(|holiday| holiday.date())
+        );
+        assert!(data_storage.holiday(date(2025, 12, 24)).is_none());
+        assert!(data_storage.is_holiday(date(2025, 11, 1)));
+        assert!(!data_storage.is_holiday(date(2025, 12, 26)));
+        assert_eq!(
+            Some("Christmas Day"),
+            data_storage.holiday_name(date(2025, 12, 25), Language::English)
+        );
+        assert_eq!(
+            Some("Weihnachtstag"),
+            data_storage.holiday_name(date(2025, 12, 25), Language::German)
+        );
+        assert_eq!(
+            None,
+            data_storage.holiday_name(date(2025, 12, 24), Language::German)
+        );
+    }
+
+    #[test]
+    fn day_type() {
+        let data_storage = fixture_data_storage();
+
+        assert_eq!(DayType::Weekday, data_storage.day_type(date(2025, 12, 24)));
+        assert_eq!(DayType::Holiday, data_storage.day_type(date(2025, 12, 25)));
+        assert_eq!(DayType::Saturday, data_storage.day_type(date(2025, 12, 27)));
+        assert_eq!(DayType::Sunday, data_storage.day_type(date(2025, 12, 28)));
+        // 1 Nov 2025 is a Saturday, the holiday wins.
+        assert_eq!(DayType::Holiday, data_storage.day_type(date(2025, 11, 1)));
+    }
+
+    #[test]
+    fn count_by_day_type() {
+        let data_storage = fixture_data_storage();
+
+        // 2025 has 261 weekdays, 52 Saturdays and 52 Sundays; 25 Dec is a Thursday and 1 Nov a Saturday.
+        let counts = data_storage.count_by_day_type(&data_storage.service_calendar(None));
+        assert_eq!(
+            FxHashMap::from_iter([
+                (DayType::Weekday, 260),
+                (DayType::Saturday, 51),
+                (DayType::Sunday, 52),
+                (DayType::Holiday, 2),
+            ]),
+            counts
+        );
+        // The bit field 3 runs on Wednesday 1 and Thursday 2 Jan.
+        assert_eq!(
+            FxHashMap::from_iter([(DayType::Weekday, 2)]),
+            data_storage.count_by_day_type(&data_storage.service_calendar(Some(3)))
+        );
+        assert!(
+            data_storage
+                .count_by_day_type(&data_storage.service_calendar(Some(2)))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,420 @@
+// The elementary connections of the trips, i.e. the rides from a stop to the next one, for the Connection Scan Algorithm.
+// The connections are returned in order of departure, so that they can be scanned or written to a file as they are.
+
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    iter::Peekable,
+    vec,
+};
+
+use bincode::config;
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, models::Model, storage::DataStorage, trip::DatedTrip};
+
+// ------------------------------------------------------------------------------------------------
+// --- Connection
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    departure_stop_id: i32,
+    arrival_stop_id: i32,
+    departure_at: NaiveDateTime,
+    arrival_at: NaiveDateTime,
+    journey_id: i32,
+    service_date: NaiveDate,
+    sequence: usize,
+    boarding_allowed: bool,
+    alighting_allowed: bool,
+    continues_as: Vec<(i32, NaiveDate)>,
+}
+
+impl Connection {
+    // Getters/Setters
+
+    pub fn departure_stop_id(&self) -> i32 {
+        self.departure_stop_id
+    }
+
+    pub fn arrival_stop_id(&self) -> i32 {
+        self.arrival_stop_id
+    }
+
+    pub fn departure_at(&self) -> NaiveDateTime {
+        self.departure_at
+    }
+
+    pub fn arrival_at(&self) -> NaiveDateTime {
+        self.arrival_at
+    }
+
+    pub fn journey_id(&self) -> i32 {
+        self.journey_id
+    }
+
+    /// The date on which the journey leaves its first stop. The trip is identified by (journey ID, service date).
+    pub fn service_date(&self) -> NaiveDate {
+        self.service_date
+    }
+
+    /// The position of the connection in its trip, starting at 0.
+    pub fn sequence(&self) -> usize {
+        self.sequence
+    }
+
+    /// Whether the passengers can board at the departure stop.
+    pub fn boarding_allowed(&self) -> bool {
+        self.boarding_allowed
+    }
+
+    /// Whether the passengers can alight at the arrival stop.
+    pub fn alighting_allowed(&self) -> bool {
+        self.alighting_allowed
+    }
+
+    /// The (journey ID, service date) of the trips continuing at the arrival stop (DURCHBI),
+    /// i.e. the passengers of this connection can stay seated.
+    pub fn continues_as(&self) -> &Vec<(i32, NaiveDate)> {
+        &self.continues_as
+    }
+
+    // Functions
+
+    /// Writes the connections to a compact binary file. Returns the number of connections written.
+    pub fn write_to_file<I>(path: &str, connections: I) -> Result<usize>
+    where
+        I: IntoIterator<Item = Connection>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let mut count = 0;
+        for connection in connections {
+            bincode::serde::encode_into_std_write(&connection, &mut writer, config::standard())?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
+    }
+
+    /// Reads the connections of a file written by write_to_file, in the same order.
+    pub fn read_from_file(path: &str) -> Result<impl Iterator<Item = Result<Connection>>> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        Ok(std::iter::from_fn(move || {
+            match reader.fill_buf() {
+                Ok([]) => return None,
+                Ok(_) => {}
+                Err(error) => return Some(Err(error.into())),
+            }
+            Some(
+                bincode::serde::decode_from_std_read(&mut reader, config::standard())
+                    .map_err(|error| error.into()),
+            )
+        }))
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the connections departing between the two dates (inclusive), sorted by departure date-time,
+    /// then by arrival date-time, journey ID and sequence.
+    /// Only the active sections of the trips are taken into account (see DatedTrip).
+    /// The connections are generated lazily, one service date at a time, so that they can be written to a file
+    /// without holding the whole period in memory.
+    pub fn connections_between(
+        &self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> impl Iterator<Item = Connection> + '_ {
+        // The trips departing on the start date may have left their first stop on a previous day.
+        let first_service_date = start_date - Days::new(self.max_trip_days() as u64);
+
+        ConnectionMerger {
+            data_storage: self,
+            start_date,
+            end_date,
+            next_service_date: first_service_date,
+            batches: Vec::new(),
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- ConnectionMerger
+// ------------------------------------------------------------------------------------------------
+
+/// Merges the sorted connections of the trips of each service date.
+/// A connection cannot depart before the service date of its trip, so the trips of a service date are only
+/// generated once all the connections departing on the previous days have been returned.
+/// At most max_trip_days + 1 service dates are held in memory.
+struct ConnectionMerger<'a> {
+    data_storage: &'a DataStorage,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    next_service_date: NaiveDate,
+    batches: Vec<Peekable<vec::IntoIter<Connection>>>,
+}
+
+impl ConnectionMerger<'_> {
+    fn load_next_service_date(&mut self) {
+        let service_date = self.next_service_date;
+        let departure_dates = self.start_date..=self.end_date;
+
+        let mut batch: Vec<Connection> = self
+            .data_storage
+            .trips_on(service_date)
+            .flat_map(|trip| trip_connections(&trip))
+            .filter(|connection| departure_dates.contains(&connection.departure_at.date()))
+            .collect();
+        batch.sort_by(compare);
+
+        if !batch.is_empty() {
+            self.batches.push(batch.into_iter().peekable());
+        }
+        self.next_service_date = service_date + Days::new(1);
+    }
+}
+
+impl Iterator for ConnectionMerger<'_> {
+    type Item = Connection;
+
+    fn next(&mut self) -> Option<Connection> {
+        loop {
+            let earliest = self
+                .batches
+                .iter_mut()
+                .enumerate()
+                .filter_map(|(i, batch)| batch.peek().map(|connection| (i, connection)))
+                .min_by(|(_, a), (_, b)| compare(a, b))
+                .map(|(i, connection)| (i, connection.departure_at.date()));
+            let all_loaded = self.next_service_date > self.end_date;
+
+            match earliest {
+                // All the service dates up to the departure date are loaded, nothing can depart earlier.
+                Some((i, departure_date))
+                    if all_loaded || departure_date < self.next_service_date =>
+                {
+                    let connection = self.batches[i].next();
+                    self.batches.retain_mut(|batch| batch.peek().is_some());
+                    return connection;
+                }
+                _ if !all_loaded => self.load_next_service_date(),
+                _ => return None,
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+fn compare(a: &Connection, b: &Connection) -> Ordering {
+    a.departure_at
+        .cmp(&b.departure_at)
+        .then(a.arrival_at.cmp(&b.arrival_at))
+        .then(a.journey_id.cmp(&b.journey_id))
+        .then(a.sequence.cmp(&b.sequence))
+}
+
+fn trip_connections(trip: &DatedTrip) -> Vec<Connection> {
+    let journey = trip.journey();
+    let route = journey.route();
+    let stop_times = trip.stop_times();
+
+    // The stops between two active sections are skipped.
+    let hops: Vec<_> = stop_times
+        .windows(2)
+        .filter_map(|pair| {
+            Some((
+                &pair[0],
+                &pair[1],
+                pair[0].departure_at()?,
+                pair[1].arrival_at()?,
+            ))
+        })
+        .collect();
+
+    let mut connections: Vec<Connection> = hops
+        .iter()
+        .enumerate()
+        .map(
+            |(sequence, &(from, to, departure_at, arrival_at))| Connection {
+                departure_stop_id: from.stop_id(),
+                arrival_stop_id: to.stop_id(),
+                departure_at,
+                arrival_at,
+                journey_id: journey.id(),
+                service_date: trip.service_date(),
+                sequence,
+                boarding_allowed: route[from.route_index()].boarding_allowed(),
+                alighting_allowed: route[to.route_index()].alighting_allowed(),
+                continues_as: Vec::new(),
+            },
+        )
+        .collect();
+
+    for &(next_journey, next_service_date, route_index) in trip.continues_as() {
+        if let Some(sequence) = hops
+            .iter()
+            .position(|(_, to, _, _)| to.route_index() == route_index)
+        {
+            connections[sequence]
+                .continues_as
+                .push((next_journey.id(), next_service_date));
+        }
+    }
+
+    connections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn connections_between() {
+        let data_storage = fixture_data_storage();
+        let connections: Vec<Connection> = data_storage
+            .connections_between(date("2025-03-03"), date("2025-03-03"))
+            .collect();
+
+        assert_eq!(21, connections.len());
+        assert!(
+            connections
+                .windows(2)
+                .all(|pair| pair[0].departure_at() <= pair[1].departure_at())
+        );
+        assert!(
+            connections
+                .iter()
+                .all(|connection| connection.departure_at().date() == date("2025-03-03"))
+        );
+
+        // The journey 9 of the day before leaves Beta on the next day at 12:10 (36:10).
+        let connection = connections
+            .iter()
+            .find(|connection| connection.service_date() == date("2025-03-02"))
+            .unwrap();
+        assert_eq!(
+            (9, 8500002, 8500005),
+            (
+                connection.journey_id(),
+                connection.departure_stop_id(),
+                connection.arrival_stop_id()
+            )
+        );
+        assert_eq!(datetime("2025-03-03 12:10"), connection.departure_at());
+
+        // The journey 6 is continued by the journey 7 at Beta (DURCHBI).
+        assert_eq!(6, connections[0].journey_id());
+        assert_eq!(datetime("2025-03-03 07:00"), connections[0].departure_at());
+        assert_eq!(
+            &vec![(7, date("2025-03-03"))],
+            connections[0].continues_as()
+        );
+    }
+
+    #[test]
+    fn connections_between_several_days() {
+        let data_storage = fixture_data_storage();
+        let connections: Vec<Connection> = data_storage
+            .connections_between(date("2025-03-03"), date("2025-03-05"))
+            .collect();
+
+        // The batches of the service dates are merged in the same order as the days taken one by one.
+        let expected: Vec<Connection> = ["2025-03-03", "2025-03-04", "2025-03-05"]
+            .into_iter()
+            .flat_map(|day| data_storage.connections_between(date(day), date(day)))
+            .collect();
+        assert_eq!(63, connections.len());
+        assert_eq!(expected, connections);
+        assert!(
+            connections
+                .windows(2)
+                .all(|pair| compare(&pair[0], &pair[1]) != Ordering::Greater)
+        );
+
+        assert_eq!(
+            0,
+            data_storage
+                .connections_between(date("2025-03-05"), date("2025-03-03"))
+                .count()
+        );
+    }
+
+    #[test]
+    fn boarding_and_alighting_allowed() {
+        let data_storage = fixture_data_storage();
+        let flags: Vec<_> = data_storage
+            .connections_between(date("2025-03-03"), date("2025-03-03"))
+            .filter(|connection| connection.journey_id() == 13)
+            .map(|connection| {
+                (
+                    connection.departure_stop_id(),
+                    connection.arrival_stop_id(),
+                    connection.boarding_allowed(),
+                    connection.alighting_allowed(),
+                )
+            })
+            .collect();
+
+        // The journey 13 has negative times at Beta: the passengers can neither alight nor board there.
+        assert_eq!(
+            vec![
+                (8500001, 8500002, true, false),
+                (8500002, 8500003, false, true)
+            ],
+            flags
+        );
+    }
+
+    #[test]
+    fn write_to_file_and_read_from_file() {
+        let data_storage = fixture_data_storage();
+        let path = std::env::temp_dir().join(format!(
+            "hrdf-parser-connections-{}.bin",
+            std::process::id()
+        ));
+        let path = path.to_str().unwrap();
+
+        let count = Connection::write_to_file(
+            path,
+            data_storage.connections_between(date("2025-03-03"), date("2025-03-04")),
+        )
+        .unwrap();
+        let connections: Vec<Connection> = Connection::read_from_file(path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(count, connections.len());
+        assert_eq!(
+            data_storage
+                .connections_between(date("2025-03-03"), date("2025-03-04"))
+                .collect::<Vec<_>>(),
+            connections
+        );
+        assert_eq!(
+            &vec![(7, date("2025-03-03"))],
+            connections[0].continues_as()
+        );
+    }
+}
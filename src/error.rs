@@ -77,6 +77,8 @@ pub(crate) enum ErrorKind {
     MissingStopName,
     #[error("Missing designation")]
     MissingDesignation,
+    #[error("Invalid SLOID: {value:?}")]
+    InvalidSloid { value: String },
     #[error("The start column is out of range.")]
     TheStartColumnIsOutOfRange,
     #[error("This type of row is unknown:\n{row}")]
@@ -0,0 +1,356 @@
+use chrono::NaiveDate;
+
+use crate::{
+    models::{Journey, LineInfo, Model},
+    storage::DataStorage,
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- ExchangeTime
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExchangeTime {
+    duration: i16,
+    is_guaranteed: bool,
+    source: ExchangeTimeSource,
+}
+
+impl ExchangeTime {
+    // Getters/Setters
+
+    /// The minimum exchange time in minutes, including the check-out (*CO) and check-in (*CI) times.
+    pub fn duration(&self) -> i16 {
+        self.duration
+    }
+
+    /// A guaranteed exchange is always possible, the departing journey waits for the arriving one.
+    pub fn is_guaranteed(&self) -> bool {
+        self.is_guaranteed
+    }
+
+    pub fn source(&self) -> ExchangeTimeSource {
+        self.source
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- ExchangeTimeSource
+// ------------------------------------------------------------------------------------------------
+
+/// The file from which the exchange time comes, in order of precedence.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExchangeTimeSource {
+    Journey,        // UMSTEIGZ
+    Line,           // UMSTEIGL
+    Administration, // UMSTEIGV
+    Stop,           // UMSTEIGB
+    Default,        // UMSTEIGB (stop 9999999)
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the minimum exchange time between the arriving journey at the route entry at arrival_route_index
+    /// and the departing journey at the route entry at departure_route_index.
+    /// Returns None if the two route entries are not at the same stop.
+    /// The date is the one on which the arriving journey leaves its first stop, it is used to check the validity of the UMSTEIGZ entries.
+    /// Precedence: UMSTEIGZ, UMSTEIGL (stop-specific entries and entries without wildcards first), UMSTEIGV (stop-specific entries first), UMSTEIGB, then the default exchange time.
+    pub fn exchange_time(
+        &self,
+        (arriving_journey, arrival_route_index): (&Journey, usize),
+        (departing_journey, departure_route_index): (&Journey, usize),
+        date: NaiveDate,
+    ) -> Option<ExchangeTime> {
+        let stop_id = arriving_journey.route().get(arrival_route_index)?.stop_id();
+        if departing_journey
+            .route()
+            .get(departure_route_index)?
+            .stop_id()
+            != stop_id
+        {
+            return None;
+        }
+
+        let (duration, is_guaranteed, source) = self
+            .journey_exchange_time(stop_id, arriving_journey, departing_journey, date)
+            .or_else(|| {
+                self.line_exchange_time(
+                    stop_id,
+                    (arriving_journey, arrival_route_index),
+                    (departing_journey, departure_route_index),
+                )
+            })
+            .or_else(|| {
+                self.administration_exchange_time(stop_id, arriving_journey, departing_journey)
+            })
+            .unwrap_or_else(|| {
+                self.stop_exchange_time(
+                    stop_id,
+                    (arriving_journey, arrival_route_index),
+                    (departing_journey, departure_route_index),
+                )
+            });
+
+        let check_out = arriving_journey.check_out_minutes_at(arrival_route_index);
+        let check_in = departing_journey.check_in_minutes_at(departure_route_index);
+
+        Some(ExchangeTime {
+            duration: duration + check_out + check_in,
+            is_guaranteed,
+            source,
+        })
+    }
+
+    // UMSTEIGZ
+    fn journey_exchange_time(
+        &self,
+        stop_id: i32,
+        arriving_journey: &Journey,
+        departing_journey: &Journey,
+        date: NaiveDate,
+    ) -> Option<(i16, bool, ExchangeTimeSource)> {
+        let key = (
+            stop_id,
+            arriving_journey.journey_id(),
+            departing_journey.journey_id(),
+        );
+
+        let mut exchange_times: Vec<_> = self
+            .exchange_times_journey_map()
+            .get(&key)?
+            .iter()
+            .filter_map(|&id| self.exchange_times_journey().find(id))
+            .filter(|exchange_time| self.is_bit_field_active(exchange_time.bit_field_id(), date))
+            .collect();
+        // Sorted to make the result deterministic if several entries are valid on the same day.
+        exchange_times.sort_by_key(|exchange_time| exchange_time.id());
+
+        exchange_times.first().map(|exchange_time| {
+            (
+                exchange_time.duration(),
+                exchange_time.is_guaranteed(),
+                ExchangeTimeSource::Journey,
+            )
+        })
+    }
+
+    // UMSTEIGL
+    fn line_exchange_time(
+        &self,
+        stop_id: i32,
+        (arriving_journey, arrival_route_index): (&Journey, usize),
+        (departing_journey, departure_route_index): (&Journey, usize),
+    ) -> Option<(i16, bool, ExchangeTimeSource)> {
+        [Some(stop_id), None]
+            .into_iter()
+            .filter_map(|stop_id| self.exchange_times_line_by_stop_id().get(&stop_id))
+            .find_map(|ids| {
+                ids.iter()
+                    .filter_map(|&id| self.exchange_times_line().find(id))
+                    .filter(|exchange_time| {
+                        self.line_matches(
+                            exchange_time.line_1(),
+                            arriving_journey,
+                            arrival_route_index,
+                        ) && self.line_matches(
+                            exchange_time.line_2(),
+                            departing_journey,
+                            departure_route_index,
+                        )
+                    })
+                    // The most specific entry wins, i.e. the one with the fewest wildcards.
+                    .max_by_key(|exchange_time| {
+                        let specificity = |line_info: &LineInfo| {
+                            usize::from(line_info.line_id().is_some())
+                                + usize::from(line_info.direction().is_some())
+                        };
+                        (
+                            specificity(exchange_time.line_1())
+                                + specificity(exchange_time.line_2()),
+                            -exchange_time.id(),
+                        )
+                    })
+            })
+            .map(|exchange_time| {
+                (
+                    exchange_time.duration(),
+                    exchange_time.is_guaranteed(),
+                    ExchangeTimeSource::Line,
+                )
+            })
+    }
+
+    fn line_matches(&self, line_info: &LineInfo, journey: &Journey, route_index: usize) -> bool {
+        line_info.administration() == journey.administration()
+            && line_info.transport_type_id() == journey.transport_type_id_at(route_index)
+            && line_info.line_id().is_none_or(|line_id| {
+                journey.line_designation_at(self, route_index) == Some(line_id)
+            })
+            && line_info
+                .direction()
+                .is_none_or(|direction| journey.direction_type_at(route_index) == Some(direction))
+    }
+
+    // UMSTEIGV
+    fn administration_exchange_time(
+        &self,
+        stop_id: i32,
+        arriving_journey: &Journey,
+        departing_journey: &Journey,
+    ) -> Option<(i16, bool, ExchangeTimeSource)> {
+        let administration_1 = arriving_journey.administration();
+        let administration_2 = departing_journey.administration();
+
+        [Some(stop_id), None].into_iter().find_map(|stop_id| {
+            let key = (
+                stop_id,
+                administration_1.to_owned(),
+                administration_2.to_owned(),
+            );
+
+            self.exchange_times_administration_map()
+                .get(&key)
+                .and_then(|&id| self.exchange_times_administration().find(id))
+                .map(|exchange_time| {
+                    (
+                        exchange_time.duration(),
+                        false,
+                        ExchangeTimeSource::Administration,
+                    )
+                })
+        })
+    }
+
+    // UMSTEIGB
+    fn stop_exchange_time(
+        &self,
+        stop_id: i32,
+        (arriving_journey, arrival_route_index): (&Journey, usize),
+        (departing_journey, departure_route_index): (&Journey, usize),
+    ) -> (i16, bool, ExchangeTimeSource) {
+        let (exchange_time, source) = match self
+            .stops()
+            .find(stop_id)
+            .and_then(|stop| stop.exchange_time())
+        {
+            Some(exchange_time) => (exchange_time, ExchangeTimeSource::Stop),
+            None => (self.default_exchange_time(), ExchangeTimeSource::Default),
+        };
+        let (inter_city, other) = exchange_time;
+
+        let is_inter_city = |journey: &Journey, route_index: usize| {
+            self.transport_types()
+                .find(journey.transport_type_id_at(route_index))
+                .is_some_and(|transport_type| transport_type.is_inter_city())
+        };
+
+        // The first exchange time only applies between two long-distance journeys (see TransportType::is_inter_city).
+        if is_inter_city(arriving_journey, arrival_route_index)
+            && is_inter_city(departing_journey, departure_route_index)
+        {
+            (inter_city, false, source)
+        } else {
+            (other, false, source)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    /// Returns the (duration, is guaranteed, source) of the exchange between the two (journey ID, route index).
+    fn exchange(
+        arrival: (i32, usize),
+        departure: (i32, usize),
+    ) -> Option<(i16, bool, ExchangeTimeSource)> {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+
+        data_storage
+            .exchange_time(
+                (journey(arrival.0), arrival.1),
+                (journey(departure.0), departure.1),
+                date,
+            )
+            .map(|exchange_time| {
+                (
+                    exchange_time.duration(),
+                    exchange_time.is_guaranteed(),
+                    exchange_time.source(),
+                )
+            })
+    }
+
+    #[test]
+    fn journey_exchange_time() {
+        // Journey 6 to journey 1 at Beta.
+        assert_eq!(
+            Some((3, true, ExchangeTimeSource::Journey)),
+            exchange((6, 1), (1, 1))
+        );
+    }
+
+    #[test]
+    fn inactive_journey_exchange_time_falls_back_to_line() {
+        // Journey 1 to journey 2 at Gamma, the UMSTEIGZ entry is never active.
+        // 4 minutes (UMSTEIGL) + 10 minutes of check-out (*CO) + 5 minutes of check-in (*CI).
+        assert_eq!(
+            Some((19, false, ExchangeTimeSource::Line)),
+            exchange((1, 2), (2, 0))
+        );
+    }
+
+    #[test]
+    fn most_specific_line_exchange_time_wins() {
+        // Journey 1 (IC S1) to journey 3 (B) at Gamma: the entry for the line S1 wins over the one for all lines.
+        // 4 minutes + 10 minutes of check-out (*CO).
+        assert_eq!(
+            Some((14, false, ExchangeTimeSource::Line)),
+            exchange((1, 2), (3, 0))
+        );
+    }
+
+    #[test]
+    fn administration_exchange_time() {
+        // Journey 1 (000011) to journey 12 (000801), at Beta the stop-specific entry wins.
+        assert_eq!(
+            Some((6, false, ExchangeTimeSource::Administration)),
+            exchange((1, 1), (12, 0))
+        );
+        // At Gamma, only the entry for all stops applies. 8 minutes + 10 minutes of check-out (*CO).
+        assert_eq!(
+            Some((18, false, ExchangeTimeSource::Administration)),
+            exchange((1, 2), (12, 1))
+        );
+    }
+
+    #[test]
+    fn stop_exchange_time() {
+        // Journey 1 (IC) to journey 11 (B) at Beta.
+        assert_eq!(
+            Some((4, false, ExchangeTimeSource::Stop)),
+            exchange((1, 1), (11, 0))
+        );
+    }
+
+    #[test]
+    fn default_exchange_time() {
+        // Journey 5 (B) to journey 3 (B) at Gamma, which has no UMSTEIGB entry.
+        assert_eq!(
+            Some((5, false, ExchangeTimeSource::Default)),
+            exchange((5, 1), (3, 0))
+        );
+    }
+
+    #[test]
+    fn route_entries_at_different_stops() {
+        assert_eq!(None, exchange((1, 0), (11, 0)));
+    }
+}
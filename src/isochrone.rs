@@ -0,0 +1,322 @@
+use std::f64::consts::PI;
+
+use chrono::{Duration, NaiveDateTime};
+use serde_json::{Value, json};
+
+use crate::{
+    routing::{Router, RoutingOptions},
+    storage::DataStorage,
+};
+
+// Number of vertices used to approximate the circle around a stop.
+const CIRCLE_SEGMENTS: usize = 32;
+// Mean meridional length of one degree of latitude, in meters.
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+// ------------------------------------------------------------------------------------------------
+// --- ReachableStop
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct ReachableStop {
+    stop_id: i32,
+    arrival_at: NaiveDateTime,
+    transfers: usize,
+}
+
+impl ReachableStop {
+    // Getters/Setters
+
+    pub fn stop_id(&self) -> i32 {
+        self.stop_id
+    }
+
+    pub fn arrival_at(&self) -> NaiveDateTime {
+        self.arrival_at
+    }
+
+    /// The number of transfers of the route arriving the earliest.
+    pub fn transfers(&self) -> usize {
+        self.transfers
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Isochrone
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct Isochrone {
+    departure_stop_id: i32,
+    departure_at: NaiveDateTime,
+    max_duration: Duration,
+    stops: Vec<ReachableStop>,
+}
+
+impl Isochrone {
+    // Getters/Setters
+
+    pub fn departure_stop_id(&self) -> i32 {
+        self.departure_stop_id
+    }
+
+    pub fn departure_at(&self) -> NaiveDateTime {
+        self.departure_at
+    }
+
+    pub fn max_duration(&self) -> Duration {
+        self.max_duration
+    }
+
+    /// The reachable stops, sorted by arrival date-time. The departure stop is included.
+    pub fn stops(&self) -> &Vec<ReachableStop> {
+        &self.stops
+    }
+
+    // Functions
+
+    /// Returns a GeoJSON FeatureCollection containing a Point for each reachable stop.
+    /// Stops without WGS84 coordinates are skipped.
+    pub fn to_geojson_points(&self, data_storage: &DataStorage) -> Value {
+        let features: Vec<Value> = self
+            .stops
+            .iter()
+            .filter_map(|stop| {
+                let (latitude, longitude) = wgs84_coordinates_of(data_storage, stop.stop_id)?;
+
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Point",
+                        "coordinates": [longitude, latitude],
+                    },
+                    "properties": self.properties_of(data_storage, stop),
+                }))
+            })
+            .collect();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+
+    /// Returns a GeoJSON FeatureCollection containing a Polygon for each reachable stop.
+    /// The polygon is a circle whose radius is the distance that can be walked in the remaining time.
+    /// The walking speed is in meters per minute (e.g. 80.0 for about 5 km/h).
+    /// Stops without WGS84 coordinates are skipped. The polygons are not merged.
+    pub fn to_geojson_polygons(&self, data_storage: &DataStorage, walking_speed: f64) -> Value {
+        let latest_arrival_at = self.departure_at + self.max_duration;
+
+        let features: Vec<Value> = self
+            .stops
+            .iter()
+            .filter_map(|stop| {
+                let (latitude, longitude) = wgs84_coordinates_of(data_storage, stop.stop_id)?;
+                let remaining_minutes = (latest_arrival_at - stop.arrival_at).num_minutes();
+                let radius = remaining_minutes as f64 * walking_speed;
+
+                let mut properties = self.properties_of(data_storage, stop);
+                properties["radius"] = json!(radius);
+
+                Some(json!({
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [circle(latitude, longitude, radius)],
+                    },
+                    "properties": properties,
+                }))
+            })
+            .collect();
+
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    }
+
+    fn properties_of(&self, data_storage: &DataStorage, stop: &ReachableStop) -> Value {
+        let stop_data = data_storage.stops().find(stop.stop_id);
+
+        json!({
+            "stop_id": stop.stop_id,
+            "name": stop_data.map(|s| s.name()),
+            "altitude": stop_data.and_then(|s| s.altitude()),
+            "arrival_at": stop.arrival_at.to_string(),
+            "duration": (stop.arrival_at - self.departure_at).num_minutes(),
+            "transfers": stop.transfers,
+        })
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns all the stops reachable from the departure stop within options.max_duration, with their earliest arrival.
+    /// The departure stop can be a stop group (METABHF), in which case the journey can start from any member of the group.
+    pub fn isochrone(
+        &self,
+        departure_stop_id: i32,
+        departure_at: NaiveDateTime,
+        options: &RoutingOptions,
+    ) -> Isochrone {
+        let mut router = Router::new(self, None, departure_at, options);
+        router.run(&self.resolve_endpoint_stop_ids(departure_stop_id));
+
+        let mut stops: Vec<ReachableStop> = router
+            .reached_stops()
+            .into_iter()
+            .map(|(stop_id, arrival_at, transfers)| ReachableStop {
+                stop_id,
+                arrival_at,
+                transfers,
+            })
+            .collect();
+        stops.sort_by_key(|stop| (stop.arrival_at, stop.stop_id));
+
+        Isochrone {
+            departure_stop_id,
+            departure_at,
+            max_duration: options.max_duration(),
+            stops,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+fn wgs84_coordinates_of(data_storage: &DataStorage, stop_id: i32) -> Option<(f64, f64)> {
+    let coordinates = data_storage.stops().find(stop_id)?.wgs84_coordinates()?;
+    Some((coordinates.latitude()?, coordinates.longitude()?))
+}
+
+/// Returns the closed ring, in counterclockwise order, of a circle around the point.
+/// An equirectangular approximation is used, which is accurate enough for radii of a few kilometers.
+fn circle(latitude: f64, longitude: f64, radius: f64) -> Vec<[f64; 2]> {
+    let delta_latitude = radius / METERS_PER_DEGREE;
+    let delta_longitude = radius / (METERS_PER_DEGREE * latitude.to_radians().cos());
+
+    let mut ring: Vec<[f64; 2]> = (0..CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / CIRCLE_SEGMENTS as f64;
+            [
+                longitude + delta_longitude * angle.cos(),
+                latitude + delta_latitude * angle.sin(),
+            ]
+        })
+        .collect();
+    ring.push(ring[0]);
+    ring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn fixture_isochrone(data_storage: &DataStorage) -> Isochrone {
+        let mut options = RoutingOptions::new();
+        options.set_max_duration(Duration::minutes(90));
+        data_storage.isochrone(8500001, datetime("2025-03-03 07:50"), &options)
+    }
+
+    fn summarize(isochrone: &Isochrone) -> Vec<(i32, NaiveDateTime, usize)> {
+        isochrone
+            .stops()
+            .iter()
+            .map(|stop| (stop.stop_id(), stop.arrival_at(), stop.transfers()))
+            .collect()
+    }
+
+    #[test]
+    fn isochrone() {
+        let data_storage = fixture_data_storage();
+        let isochrone = fixture_isochrone(data_storage);
+
+        // Epsilon is not reached: Gamma cannot be used as a via (BHFART), so no footpath starts there.
+        assert_eq!(
+            summarize(&isochrone),
+            vec![
+                (8500001, datetime("2025-03-03 07:50"), 0),
+                (8500002, datetime("2025-03-03 08:30"), 0),
+                (8500003, datetime("2025-03-03 09:00"), 0),
+                (8500004, datetime("2025-03-03 09:20"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn isochrone_max_duration_cutoff() {
+        let data_storage = fixture_data_storage();
+        let mut options = RoutingOptions::new();
+        options.set_max_duration(Duration::minutes(60));
+        let isochrone = data_storage.isochrone(8500001, datetime("2025-03-03 07:50"), &options);
+
+        assert_eq!(isochrone.max_duration(), Duration::minutes(60));
+        assert_eq!(
+            summarize(&isochrone),
+            vec![
+                (8500001, datetime("2025-03-03 07:50"), 0),
+                (8500002, datetime("2025-03-03 08:30"), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn to_geojson_points() {
+        let data_storage = fixture_data_storage();
+        let geojson = fixture_isochrone(data_storage).to_geojson_points(data_storage);
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 4);
+        assert!(features.iter().all(|f| f["geometry"]["type"] == "Point"));
+
+        let beta = &features[1];
+        assert_eq!(beta["geometry"]["coordinates"], json!([7.41, 46.91]));
+        assert_eq!(
+            beta["properties"],
+            json!({
+                "stop_id": 8500002,
+                "name": "Beta",
+                "altitude": 500,
+                "arrival_at": "2025-03-03 08:30:00",
+                "duration": 40,
+                "transfers": 0,
+            })
+        );
+    }
+
+    #[test]
+    fn to_geojson_polygons() {
+        let data_storage = fixture_data_storage();
+        let geojson = fixture_isochrone(data_storage).to_geojson_polygons(data_storage, 80.0);
+
+        assert_eq!(geojson["type"], "FeatureCollection");
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 4);
+
+        for feature in features {
+            assert_eq!(feature["geometry"]["type"], "Polygon");
+            let ring = feature["geometry"]["coordinates"][0].as_array().unwrap();
+            assert_eq!(ring.len(), CIRCLE_SEGMENTS + 1);
+            assert_eq!(ring.first(), ring.last());
+        }
+
+        // 09:20 - 08:30 = 50 minutes of walking at 80 m/min.
+        assert_eq!(features[1]["properties"]["stop_id"], 8500002);
+        assert_eq!(features[1]["properties"]["radius"], 4000.0);
+        // Delta is reached at the very end of the isochrone.
+        assert_eq!(features[3]["properties"]["radius"], 0.0);
+    }
+}
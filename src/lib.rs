@@ -1,15 +1,35 @@
+mod board;
+mod calendar;
+mod connection;
 mod error;
+mod exchange;
 mod hrdf;
+mod isochrone;
 mod models;
 mod parsing;
+mod routing;
+mod search;
+mod sloid;
+mod spatial;
 mod storage;
+mod trip;
 mod utils;
 
+pub use board::{BoardEvent, BoardFilter, BoardStop};
+pub use calendar::{DayType, ServiceCalendar};
+pub use connection::Connection;
 pub use error::Error;
 pub use error::Result;
+pub use exchange::{ExchangeTime, ExchangeTimeSource};
 pub use hrdf::Hrdf;
+pub use isochrone::{Isochrone, ReachableStop};
 pub use models::*;
+pub use routing::{Route, RouteSection, RoutingOptions};
+pub use search::{StopSearchIndex, StopSearchResult};
+pub use sloid::Sloid;
+pub use spatial::KdTree;
 pub use storage::DataStorage;
+pub use trip::{DatedStopTime, DatedTrip};
 pub use utils::timetable_end_date;
 pub use utils::timetable_start_date;
 
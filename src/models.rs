@@ -1,6 +1,8 @@
 use std::{
     collections::BTreeSet,
     hash::{DefaultHasher, Hash, Hasher},
+    ops::BitOr,
+    str::FromStr,
 };
 
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
@@ -9,12 +11,17 @@ use serde::{Deserialize, Serialize};
 use strum_macros::{self, Display, EnumString};
 
 use crate::{
-    storage::DataStorage,
+    calendar::ServiceCalendar,
+    sloid::Sloid,
+    storage::{DataStorage, ResourceStorage},
     utils::{add_1_day, sub_1_day},
 };
 
 pub(crate) type JourneyId = (i32, String); // (legacy_id, administration)
 
+// Mean radius of the Earth, in meters.
+pub(crate) const EARTH_RADIUS: f64 = 6_371_008.8;
+
 // ------------------------------------------------------------------------------------------------
 // --- Model
 // ------------------------------------------------------------------------------------------------
@@ -58,6 +65,8 @@ pub struct Attribute {
     main_sorting_priority: i16,
     secondary_sorting_priority: i16,
     description: FxHashMap<Language, String>,
+    partial_route_designation: Option<String>,
+    full_route_designation: Option<String>,
 }
 
 impl_Model!(Attribute);
@@ -77,37 +86,169 @@ impl Attribute {
             main_sorting_priority,
             secondary_sorting_priority,
             description: FxHashMap::default(),
+            partial_route_designation: None,
+            full_route_designation: None,
         }
     }
 
     // Getters/Setters
 
+    pub fn designation(&self) -> &str {
+        &self.designation
+    }
+
+    /// 0: the attribute applies to the journey section,
+    /// 1: only to the departure stop of the section,
+    /// 2: only to the arrival stop of the section,
+    /// 3: to the departure and arrival stops of the section.
+    pub fn stop_scope(&self) -> i16 {
+        self.stop_scope
+    }
+
+    pub fn main_sorting_priority(&self) -> i16 {
+        self.main_sorting_priority
+    }
+
+    pub fn secondary_sorting_priority(&self) -> i16 {
+        self.secondary_sorting_priority
+    }
+
+    pub fn description(&self, language: Language) -> Option<&str> {
+        self.description.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_description(&mut self, language: Language, value: &str) {
         self.description.insert(language, value.to_string());
     }
+
+    /// The designation of the attribute to display when it applies to a part of the travelled route only.
+    /// Defaults to the designation of the attribute itself.
+    pub fn partial_route_designation(&self) -> &str {
+        self.partial_route_designation
+            .as_deref()
+            .unwrap_or(&self.designation)
+    }
+
+    pub fn set_partial_route_designation(&mut self, value: String) {
+        self.partial_route_designation = Some(value);
+    }
+
+    /// The designation of the attribute to display when it applies to the whole travelled route.
+    /// Defaults to the designation of the attribute itself.
+    pub fn full_route_designation(&self) -> &str {
+        self.full_route_designation
+            .as_deref()
+            .unwrap_or(&self.designation)
+    }
+
+    pub fn set_full_route_designation(&mut self, value: String) {
+        self.full_route_designation = Some(value);
+    }
+
+    // Functions
+
+    /// Returns true if the attribute, valid on the route indexes [section_start, section_end] of a journey,
+    /// must be displayed for a trip on the route indexes [start, end], according to its stop scope.
+    pub fn applies_to(
+        &self,
+        (section_start, section_end): (usize, usize),
+        (start, end): (usize, usize),
+    ) -> bool {
+        let contains = |i: usize| section_start <= i && i <= section_end;
+
+        match self.stop_scope {
+            1 => contains(start),
+            2 => contains(end),
+            3 => contains(start) || contains(end),
+            // A section reduced to a stop applies if the stop is on the travelled route.
+            _ if section_start == section_end => start <= section_start && section_start <= end,
+            // Otherwise, the section and the travelled route must share at least one hop.
+            _ => section_start < end && start < section_end,
+        }
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
 // --- BitField
 // ------------------------------------------------------------------------------------------------
 
+/// The bits are packed in 64-bit words: the bit i is the bit i % 64 of the word i / 64.
+/// The first two bits of a BITFELD entry do not correspond to any day, see ServiceCalendar for the days.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BitField {
     id: i32,
-    bits: Vec<u8>,
+    words: Vec<u64>,
+    len: usize,
 }
 
 impl_Model!(BitField);
 
 impl BitField {
+    /// Each item of bits is a bit (0 or 1).
     pub fn new(id: i32, bits: Vec<u8>) -> Self {
-        Self { id, bits }
+        let mut words = vec![0; bits.len().div_ceil(64)];
+        for (i, _) in bits.iter().enumerate().filter(|&(_, &bit)| bit == 1) {
+            words[i / 64] |= 1 << (i % 64);
+        }
+
+        Self {
+            id,
+            words,
+            len: bits.len(),
+        }
     }
 
     // Getters/Setters
 
-    pub fn bits(&self) -> &Vec<u8> {
-        &self.bits
+    pub fn words(&self) -> &Vec<u64> {
+        &self.words
+    }
+
+    /// The number of bits.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Functions
+
+    /// Returns the bit at the given index, false if the index is out of range.
+    pub fn bit(&self, index: usize) -> bool {
+        index < self.len && (self.words[index / 64] >> (index % 64)) & 1 == 1
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- BorderPoint
+// ------------------------------------------------------------------------------------------------
+
+/// A stop where journeys cross a (tariff) border (GRENZHLT). The ID is the ID of the stop.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BorderPoint {
+    id: i32,
+    description: Option<String>,
+}
+
+impl_Model!(BorderPoint);
+
+impl BorderPoint {
+    pub fn new(id: i32, description: Option<String>) -> Self {
+        Self { id, description }
+    }
+
+    // Getters/Setters
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    // Functions
+
+    pub fn stop<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a Stop> {
+        data_storage.stops().find(self.id)
     }
 }
 
@@ -204,6 +345,86 @@ impl Coordinates {
             CoordinateSystem::LV95 => None,
         }
     }
+
+    pub fn coordinate_system(&self) -> CoordinateSystem {
+        self.coordinate_system
+    }
+
+    // Functions
+
+    /// Converts the coordinates to WGS84 using the approximate formulas published by swisstopo.
+    /// The error is less than 1 meter within Switzerland.
+    pub fn to_wgs84(&self) -> Coordinates {
+        match self.coordinate_system {
+            CoordinateSystem::WGS84 => *self,
+            CoordinateSystem::LV95 => {
+                // Auxiliary values, in units of 1000 km from Bern.
+                let y = (self.x - 2_600_000.0) / 1_000_000.0;
+                let x = (self.y - 1_200_000.0) / 1_000_000.0;
+
+                // Results in units of 10000".
+                let longitude = 2.6779094 + 4.728982 * y + 0.791484 * y * x + 0.1306 * y * x * x
+                    - 0.0436 * y * y * y;
+                let latitude = 16.9023892 + 3.238272 * x
+                    - 0.270978 * y * y
+                    - 0.002528 * x * x
+                    - 0.0447 * y * y * x
+                    - 0.0140 * x * x * x;
+
+                Coordinates::new(
+                    CoordinateSystem::WGS84,
+                    latitude * 100.0 / 36.0,
+                    longitude * 100.0 / 36.0,
+                )
+            }
+        }
+    }
+
+    /// Converts the coordinates to LV95 using the approximate formulas published by swisstopo.
+    /// The error is less than 1 meter within Switzerland.
+    pub fn to_lv95(&self) -> Coordinates {
+        match self.coordinate_system {
+            CoordinateSystem::LV95 => *self,
+            CoordinateSystem::WGS84 => {
+                // Auxiliary values, in units of 10000" from Bern.
+                let latitude = (self.x * 3600.0 - 169_028.66) / 10_000.0;
+                let longitude = (self.y * 3600.0 - 26_782.5) / 10_000.0;
+
+                let easting = 2_600_072.37 + 211_455.93 * longitude
+                    - 10_938.51 * longitude * latitude
+                    - 0.36 * longitude * latitude * latitude
+                    - 44.54 * longitude * longitude * longitude;
+                let northing = 1_200_147.07
+                    + 308_807.95 * latitude
+                    + 3_745.25 * longitude * longitude
+                    + 76.63 * latitude * latitude
+                    - 194.56 * longitude * longitude * latitude
+                    + 119.79 * latitude * latitude * latitude;
+
+                Coordinates::new(CoordinateSystem::LV95, easting, northing)
+            }
+        }
+    }
+
+    /// Returns the great-circle distance in meters, computed with the haversine formula on the WGS84 coordinates.
+    pub fn haversine_distance(&self, other: &Coordinates) -> f64 {
+        let (a, b) = (self.to_wgs84(), other.to_wgs84());
+
+        let delta_latitude = (b.x - a.x).to_radians();
+        let delta_longitude = (b.y - a.y).to_radians();
+        let h = (delta_latitude / 2.0).sin().powi(2)
+            + a.x.to_radians().cos()
+                * b.x.to_radians().cos()
+                * (delta_longitude / 2.0).sin().powi(2);
+
+        2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+    }
+
+    /// Returns the Euclidean distance in meters between the LV95 coordinates.
+    pub fn euclidean_distance(&self, other: &Coordinates) -> f64 {
+        let (a, b) = (self.to_lv95(), other.to_lv95());
+        (b.x - a.x).hypot(b.y - a.y)
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -222,21 +443,30 @@ impl Direction {
     pub fn new(id: i32, name: String) -> Self {
         Self { id, name }
     }
+
+    // Getters/Setters
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
 // --- DirectionType
 // ------------------------------------------------------------------------------------------------
 
+/// The direction flag of the *R rows of FPLAN and of the UMSTEIGL file.
 #[derive(
     Clone, Copy, Debug, Default, Display, Eq, Hash, PartialEq, EnumString, Serialize, Deserialize,
 )]
 pub enum DirectionType {
+    /// Forward direction ("Hinrichtung").
     #[default]
-    #[strum(serialize = "R")]
+    #[strum(serialize = "H")]
     Outbound,
 
-    #[strum(serialize = "H")]
+    /// Backward direction ("Rückrichtung").
+    #[strum(serialize = "R")]
     Return,
 }
 
@@ -257,6 +487,16 @@ impl Holiday {
     pub fn new(id: i32, date: NaiveDate, name: FxHashMap<Language, String>) -> Self {
         Self { id, date, name }
     }
+
+    // Getters/Setters
+
+    pub fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub fn name(&self, language: Language) -> Option<&str> {
+        self.name.get(&language).map(|s| s.as_str())
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -378,6 +618,10 @@ impl ExchangeTimeJourney {
         self.duration
     }
 
+    pub fn is_guaranteed(&self) -> bool {
+        self.is_guaranteed
+    }
+
     pub fn bit_field_id(&self) -> Option<i32> {
         self.bit_field_id
     }
@@ -400,7 +644,7 @@ pub struct ExchangeTimeLine {
 impl_Model!(ExchangeTimeLine);
 
 #[derive(Debug, Serialize, Deserialize)]
-pub(crate) struct LineInfo {
+pub struct LineInfo {
     administration: String,
     transport_type_id: i32,
     line_id: Option<String>,
@@ -421,6 +665,26 @@ impl LineInfo {
             direction,
         }
     }
+
+    // Getters/Setters
+
+    pub fn administration(&self) -> &str {
+        &self.administration
+    }
+
+    pub fn transport_type_id(&self) -> i32 {
+        self.transport_type_id
+    }
+
+    /// A None value means that the exchange time applies to all lines (* in the file).
+    pub fn line_id(&self) -> Option<&str> {
+        self.line_id.as_deref()
+    }
+
+    /// A None value means that the exchange time applies to both directions (* in the file).
+    pub fn direction(&self) -> Option<DirectionType> {
+        self.direction
+    }
 }
 
 impl ExchangeTimeLine {
@@ -441,6 +705,52 @@ impl ExchangeTimeLine {
             is_guaranteed,
         }
     }
+
+    // Getters/Setters
+
+    pub fn stop_id(&self) -> Option<i32> {
+        self.stop_id
+    }
+
+    pub fn line_1(&self) -> &LineInfo {
+        &self.line_1
+    }
+
+    pub fn line_2(&self) -> &LineInfo {
+        &self.line_2
+    }
+
+    pub fn duration(&self) -> i16 {
+        self.duration
+    }
+
+    pub fn is_guaranteed(&self) -> bool {
+        self.is_guaranteed
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- InfoTextCode
+// ------------------------------------------------------------------------------------------------
+
+/// The INFOTEXTCODE of a *I row, telling whether the information text is a simple text or a value with a semantic meaning.
+#[derive(Clone, Debug, Display, Eq, Hash, PartialEq, EnumString, Serialize, Deserialize)]
+pub enum InfoTextCode {
+    /// Swiss Journey ID (SJYID), e.g. "ch:1:sjyid:100001:3-002".
+    #[strum(serialize = "JY")]
+    SwissJourneyId,
+
+    #[strum(serialize = "RN")]
+    Region,
+
+    #[strum(serialize = "ZN")]
+    TrainName,
+
+    #[strum(serialize = "hi")]
+    Hint,
+
+    #[strum(default)]
+    Other(String),
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -465,9 +775,31 @@ impl InformationText {
 
     // Getters/Setters
 
+    pub fn content(&self, language: Language) -> Option<&str> {
+        self.content.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_content(&mut self, language: Language, value: &str) {
         self.content.insert(language, value.to_string());
     }
+
+    pub fn contents(&self) -> &FxHashMap<Language, String> {
+        &self.content
+    }
+
+    // Functions
+
+    /// Returns the value of a text with a semantic meaning (e.g. a SJYID), which is the same in all languages.
+    pub fn value(&self) -> Option<&str> {
+        [
+            Language::German,
+            Language::French,
+            Language::Italian,
+            Language::English,
+        ]
+        .into_iter()
+        .find_map(|language| self.content(language))
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -506,7 +838,12 @@ impl Journey {
         self.legacy_id
     }
 
-    fn metadata(&self) -> &FxHashMap<JourneyMetadataType, Vec<JourneyMetadataEntry>> {
+    /// The (legacy ID, administration) pair used in the other files to reference the journey.
+    pub(crate) fn journey_id(&self) -> JourneyId {
+        (self.legacy_id, self.administration.to_owned())
+    }
+
+    pub fn metadata(&self) -> &FxHashMap<JourneyMetadataType, Vec<JourneyMetadataEntry>> {
         &self.metadata
     }
 
@@ -530,6 +867,11 @@ impl Journey {
         entry.bit_field_id
     }
 
+    /// Returns the dates on which the journey leaves its first stop.
+    pub fn operating_dates(&self, data_storage: &DataStorage) -> ServiceCalendar {
+        data_storage.service_calendar(self.bit_field_id())
+    }
+
     pub fn transport_type_id(&self) -> i32 {
         // unwrap: There will always be a TransportType entry.
         let entry = &self
@@ -547,6 +889,13 @@ impl Journey {
             .unwrap_or_else(|| panic!("Transport type {:?} not found.", self.transport_type_id()))
     }
 
+    pub fn transport_company<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+    ) -> Option<&'a TransportCompany> {
+        data_storage.transport_company_by_administration(self.administration())
+    }
+
     pub fn first_stop_id(&self) -> i32 {
         // unwrap: The route always contains at least 2 entries.
         self.route.first().unwrap().stop_id()
@@ -684,6 +1033,381 @@ impl Journey {
         }
     }
 
+    /// The date must correspond to the route's first entry.
+    /// Returns the (arrival, departure) date-times of each route entry, in route order.
+    /// A time earlier than the previous time of the route is considered to be on the next day.
+    pub fn route_datetimes(
+        &self,
+        date: NaiveDate,
+    ) -> Vec<(Option<NaiveDateTime>, Option<NaiveDateTime>)> {
+        let mut current_date = date;
+        let mut previous_time: Option<NaiveTime> = None;
+        let mut to_datetime = |time: &Option<NaiveTime>| {
+            time.map(|time| {
+                if previous_time.is_some_and(|previous_time| time < previous_time) {
+                    current_date = add_1_day(current_date);
+                }
+                previous_time = Some(time);
+                NaiveDateTime::new(current_date, time)
+            })
+        };
+
+        self.route()
+            .iter()
+            .map(|route_entry| {
+                let arrival_at = to_datetime(route_entry.arrival_time());
+                let departure_at = to_datetime(route_entry.departure_time());
+                (arrival_at, departure_at)
+            })
+            .collect()
+    }
+
+    /// Returns the first and last route indexes (inclusive) of the section to which the metadata entry applies.
+    /// A None value means that the section's stops are not part of the route.
+    pub fn section_of(&self, entry: &JourneyMetadataEntry) -> Option<(usize, usize)> {
+        let start = match entry.from_stop_id() {
+            Some(stop_id) => self.route.iter().position(|e| e.stop_id() == stop_id)?,
+            None => 0,
+        };
+        let end = match entry.until_stop_id() {
+            Some(stop_id) => {
+                start
+                    + self
+                        .route
+                        .iter()
+                        .skip(start)
+                        .position(|e| e.stop_id() == stop_id)?
+            }
+            None => self.route.len().saturating_sub(1),
+        };
+        Some((start, end))
+    }
+
+    /// Returns the metadata entries of the given type whose section contains the route entry at the given index.
+    pub fn metadata_entries_at(
+        &self,
+        k: JourneyMetadataType,
+        route_index: usize,
+    ) -> Vec<&JourneyMetadataEntry> {
+        self.metadata()
+            .get(&k)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|entry| {
+                        self.section_of(entry)
+                            .is_some_and(|(start, end)| start <= route_index && route_index <= end)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the ID of the transport type valid at the route entry at the given index.
+    pub fn transport_type_id_at(&self, route_index: usize) -> i32 {
+        self.metadata_entries_at(JourneyMetadataType::TransportType, route_index)
+            .iter()
+            .find_map(|entry| entry.resource_id())
+            .unwrap_or_else(|| self.transport_type_id())
+    }
+
+    /// Returns the transport type valid at the route entry at the given index.
+    pub fn transport_type_at<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+        route_index: usize,
+    ) -> &'a TransportType {
+        let transport_type_id = self.transport_type_id_at(route_index);
+        data_storage
+            .transport_types()
+            .find(transport_type_id)
+            .unwrap_or_else(|| panic!("Transport type {:?} not found.", transport_type_id))
+    }
+
+    /// Returns the line designation (e.g. "S3" or "IC 5") valid at the route entry at the given index.
+    pub fn line_designation_at<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+        route_index: usize,
+    ) -> Option<&'a str> {
+        let entry = *self
+            .metadata_entries_at(JourneyMetadataType::Line, route_index)
+            .first()?;
+
+        match (entry.resource_id(), entry.extra_field_1()) {
+            (Some(line_id), _) => data_storage.lines().find(line_id).map(|line| {
+                if line.short_name().is_empty() {
+                    line.name()
+                } else {
+                    line.short_name()
+                }
+            }),
+            (None, designation) => designation,
+        }
+    }
+
+    /// Returns the name of the direction valid at the route entry at the given index.
+    pub fn direction_name_at<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+        route_index: usize,
+    ) -> Option<&'a str> {
+        self.metadata_entries_at(JourneyMetadataType::Direction, route_index)
+            .iter()
+            .find_map(|entry| entry.resource_id())
+            .and_then(|direction_id| data_storage.directions().find(direction_id))
+            .map(|direction| direction.name())
+    }
+
+    /// Returns the direction text (headsign) of the journey at the stop.
+    /// If the journey serves the stop several times, the first time is used.
+    /// See direction_text_at_index for the fallbacks.
+    pub fn direction_text_at<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+        stop_id: i32,
+    ) -> Option<&'a str> {
+        let route_index = self
+            .route
+            .iter()
+            .position(|route_entry| route_entry.stop_id() == stop_id)?;
+        self.direction_text_at_index(data_storage, route_index)
+    }
+
+    /// Returns the direction text (headsign) valid at the route entry at the given index.
+    /// A *R row without direction code (e.g. a bare *R H) falls back to the name of the last stop of its section,
+    /// and a route entry without *R row falls back to the name of the last stop of the journey.
+    pub fn direction_text_at_index<'a>(
+        &'a self,
+        data_storage: &'a DataStorage,
+        route_index: usize,
+    ) -> Option<&'a str> {
+        if route_index >= self.route.len() {
+            return None;
+        }
+
+        let entries = self.metadata_entries_at(JourneyMetadataType::Direction, route_index);
+        if let Some(name) = entries
+            .iter()
+            .find_map(|entry| entry.resource_id())
+            .and_then(|direction_id| data_storage.directions().find(direction_id))
+            .map(|direction| direction.name())
+        {
+            return Some(name);
+        }
+
+        let last_route_index = entries
+            .first()
+            .and_then(|entry| self.section_of(entry))
+            .map_or(self.route.len() - 1, |(_, end)| end);
+        data_storage
+            .stops()
+            .find(self.route[last_route_index].stop_id())
+            .map(|stop| stop.name())
+    }
+
+    /// Returns the direction type (*R H or *R R) valid at the route entry at the given index.
+    pub fn direction_type_at(&self, route_index: usize) -> Option<DirectionType> {
+        self.metadata_entries_at(JourneyMetadataType::Direction, route_index)
+            .iter()
+            .find_map(|entry| entry.extra_field_1())
+            .and_then(|direction_type| DirectionType::from_str(direction_type).ok())
+    }
+
+    /// Returns the attributes to display for a trip on the route indexes [start, end] (inclusive), sorted by
+    /// main sorting priority, then secondary sorting priority, then designation.
+    /// The partial or full route form (ATTRIBUT "#" rows) of each attribute is returned, depending on whether
+    /// the attribute applies to the whole trip or not.
+    pub fn attributes_in_section<'a>(
+        &self,
+        data_storage: &'a DataStorage,
+        start: usize,
+        end: usize,
+    ) -> Vec<&'a Attribute> {
+        let mut attributes: Vec<&Attribute> = self
+            .metadata()
+            .get(&JourneyMetadataType::Attribute)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let attribute = data_storage.attributes().find(entry.resource_id()?)?;
+                let section = self.section_of(entry)?;
+                if !attribute.applies_to(section, (start, end)) {
+                    return None;
+                }
+
+                let designation = if section.0 <= start && end <= section.1 {
+                    attribute.full_route_designation()
+                } else {
+                    attribute.partial_route_designation()
+                };
+                data_storage
+                    .attribute_by_designation(designation)
+                    .or(Some(attribute))
+            })
+            .collect();
+
+        attributes.sort_by(|a, b| {
+            a.main_sorting_priority()
+                .cmp(&b.main_sorting_priority())
+                .then(
+                    a.secondary_sorting_priority()
+                        .cmp(&b.secondary_sorting_priority()),
+                )
+                .then(a.designation().cmp(b.designation()))
+        });
+        attributes.dedup_by_key(|attribute| attribute.id());
+        attributes
+    }
+
+    /// Returns the (designation, description) pairs of the attributes to display for a trip on the route indexes
+    /// [start, end] (inclusive), in display order (see attributes_in_section).
+    /// The designation is used as description if the attribute has no description in the language.
+    pub fn render_attributes<'a>(
+        &self,
+        data_storage: &'a DataStorage,
+        start: usize,
+        end: usize,
+        language: Language,
+    ) -> Vec<(&'a str, &'a str)> {
+        self.attributes_in_section(data_storage, start, end)
+            .into_iter()
+            .map(|attribute| {
+                (
+                    attribute.designation(),
+                    attribute
+                        .description(language)
+                        .unwrap_or(attribute.designation()),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the check-in time (*CI) in minutes at the stop, i.e. how long before the departure the passengers must be there.
+    /// If the journey serves the stop several times, the first time is used.
+    pub fn check_in_minutes(&self, stop_id: i32) -> i16 {
+        self.route
+            .iter()
+            .position(|route_entry| route_entry.stop_id() == stop_id)
+            .map_or(0, |route_index| self.check_in_minutes_at(route_index))
+    }
+
+    /// Returns the check-out time (*CO) in minutes at the stop, i.e. how long after the arrival the passengers can leave.
+    /// If the journey serves the stop several times, the last time is used.
+    pub fn check_out_minutes(&self, stop_id: i32) -> i16 {
+        self.route
+            .iter()
+            .rposition(|route_entry| route_entry.stop_id() == stop_id)
+            .map_or(0, |route_index| self.check_out_minutes_at(route_index))
+    }
+
+    /// Returns the check-in time (*CI) in minutes at the route entry at the given index.
+    pub fn check_in_minutes_at(&self, route_index: usize) -> i16 {
+        self.check_minutes_at(JourneyMetadataType::ExchangeTimeBoarding, route_index)
+    }
+
+    /// Returns the check-out time (*CO) in minutes at the route entry at the given index.
+    pub fn check_out_minutes_at(&self, route_index: usize) -> i16 {
+        self.check_minutes_at(JourneyMetadataType::ExchangeTimeDisembarking, route_index)
+    }
+
+    fn check_minutes_at(&self, k: JourneyMetadataType, route_index: usize) -> i16 {
+        self.metadata_entries_at(k, route_index)
+            .iter()
+            .find_map(|entry| entry.extra_field_2())
+            .map_or(0, |minutes| minutes as i16)
+    }
+
+    /// Returns the information texts (*I) of the journey with the given code, in the order of the FPLAN file.
+    pub fn information_texts<'a>(
+        &self,
+        data_storage: &'a DataStorage,
+        code: &InfoTextCode,
+    ) -> Vec<&'a InformationText> {
+        self.metadata()
+            .get(&JourneyMetadataType::InformationText)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.info_text_code().as_ref() == Some(code))
+            .filter_map(|entry| data_storage.information_texts().find(entry.resource_id()?))
+            .collect()
+    }
+
+    /// Returns the Swiss Journey ID (SJYID) of the journey, used to match the journey with real-time data.
+    pub fn sjyid<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a str> {
+        self.information_texts(data_storage, &InfoTextCode::SwissJourneyId)
+            .into_iter()
+            .find_map(|information_text| information_text.value())
+    }
+
+    pub fn region<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a str> {
+        self.information_texts(data_storage, &InfoTextCode::Region)
+            .into_iter()
+            .find_map(|information_text| information_text.value())
+    }
+
+    pub fn train_name<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a str> {
+        self.information_texts(data_storage, &InfoTextCode::TrainName)
+            .into_iter()
+            .find_map(|information_text| information_text.value())
+    }
+
+    pub fn hints<'a>(&self, data_storage: &'a DataStorage, language: Language) -> Vec<&'a str> {
+        self.information_texts(data_storage, &InfoTextCode::Hint)
+            .into_iter()
+            .filter_map(|information_text| information_text.content(language))
+            .collect()
+    }
+
+    /// Returns the route indexes at which the journey crosses a border, i.e. the intermediate stops that are border points.
+    /// A journey starting or ending at a border point does not cross the border there.
+    pub fn border_crossings(&self, data_storage: &DataStorage) -> Vec<usize> {
+        self.border_crossings_in(data_storage.border_points())
+    }
+
+    /// Same as border_crossings, but usable while the data storage is being built.
+    pub(crate) fn border_crossings_in(
+        &self,
+        border_points: &ResourceStorage<BorderPoint>,
+    ) -> Vec<usize> {
+        let last_index = self.route.len().saturating_sub(1);
+        self.route
+            .iter()
+            .enumerate()
+            .filter(|&(i, route_entry)| {
+                0 < i && i < last_index && border_points.find(route_entry.stop_id()).is_some()
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn crosses_border(&self, data_storage: &DataStorage) -> bool {
+        !self.border_crossings(data_storage).is_empty()
+    }
+
+    /// Returns the border stops where the journey crosses a border, in route order.
+    pub fn border_stops<'a>(&self, data_storage: &'a DataStorage) -> Vec<&'a Stop> {
+        self.border_crossings(data_storage)
+            .into_iter()
+            .filter_map(|i| data_storage.stops().find(self.route[i].stop_id()))
+            .collect()
+    }
+
+    /// Returns the first and last route indexes (inclusive) of the sections between the borders.
+    /// Consecutive sections share the border stop, e.g. [(0, 4), (4, 9)] for a journey crossing a border at index 4.
+    pub fn border_sections(&self, data_storage: &DataStorage) -> Vec<(usize, usize)> {
+        let last_index = self.route.len().saturating_sub(1);
+        let mut sections = Vec::new();
+        let mut start = 0;
+
+        for i in self.border_crossings(data_storage) {
+            sections.push((start, i));
+            start = i;
+        }
+        sections.push((start, last_index));
+        sections
+    }
+
     /// Excluding departure stop.
     pub fn route_section(
         &self,
@@ -768,6 +1492,47 @@ impl JourneyMetadataEntry {
             extra_field_2,
         }
     }
+
+    // Getters/Setters
+
+    pub fn from_stop_id(&self) -> Option<i32> {
+        self.from_stop_id
+    }
+
+    pub fn until_stop_id(&self) -> Option<i32> {
+        self.until_stop_id
+    }
+
+    pub fn resource_id(&self) -> Option<i32> {
+        self.resource_id
+    }
+
+    pub fn bit_field_id(&self) -> Option<i32> {
+        self.bit_field_id
+    }
+
+    pub fn departure_time(&self) -> Option<NaiveTime> {
+        self.departure_time
+    }
+
+    pub fn arrival_time(&self) -> Option<NaiveTime> {
+        self.arrival_time
+    }
+
+    pub fn extra_field_1(&self) -> Option<&str> {
+        self.extra_field_1.as_deref()
+    }
+
+    /// The INFOTEXTCODE of an InformationText entry, stored in extra_field_1.
+    pub fn info_text_code(&self) -> Option<InfoTextCode> {
+        self.extra_field_1
+            .as_deref()
+            .and_then(|code| InfoTextCode::from_str(code).ok())
+    }
+
+    pub fn extra_field_2(&self) -> Option<i32> {
+        self.extra_field_2
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -779,6 +1544,8 @@ pub struct JourneyRouteEntry {
     stop_id: i32,
     arrival_time: Option<NaiveTime>,
     departure_time: Option<NaiveTime>,
+    alighting_allowed: bool, // A negative arrival time in FPLAN means that alighting is not allowed.
+    boarding_allowed: bool, // A negative departure time in FPLAN means that boarding is not allowed.
 }
 
 impl JourneyRouteEntry {
@@ -791,6 +1558,8 @@ impl JourneyRouteEntry {
             stop_id,
             arrival_time,
             departure_time,
+            alighting_allowed: true,
+            boarding_allowed: true,
         }
     }
 
@@ -808,6 +1577,22 @@ impl JourneyRouteEntry {
         &self.departure_time
     }
 
+    pub fn alighting_allowed(&self) -> bool {
+        self.alighting_allowed
+    }
+
+    pub fn set_alighting_allowed(&mut self, value: bool) {
+        self.alighting_allowed = value;
+    }
+
+    pub fn boarding_allowed(&self) -> bool {
+        self.boarding_allowed
+    }
+
+    pub fn set_boarding_allowed(&mut self, value: bool) {
+        self.boarding_allowed = value;
+    }
+
     // Functions
 
     pub fn stop<'a>(&'a self, data_storage: &'a DataStorage) -> &'a Stop {
@@ -824,6 +1609,7 @@ impl JourneyRouteEntry {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JourneyPlatform {
+    id: i32,
     journey_legacy_id: i32,
     administration: String,
     platform_id: i32,
@@ -831,8 +1617,11 @@ pub struct JourneyPlatform {
     bit_field_id: Option<i32>,
 }
 
+impl_Model!(JourneyPlatform);
+
 impl JourneyPlatform {
     pub fn new(
+        id: i32,
         journey_legacy_id: i32,
         administration: String,
         platform_id: i32,
@@ -840,6 +1629,7 @@ impl JourneyPlatform {
         bit_field_id: Option<i32>,
     ) -> Self {
         Self {
+            id,
             journey_legacy_id,
             administration,
             platform_id,
@@ -847,13 +1637,28 @@ impl JourneyPlatform {
             bit_field_id,
         }
     }
-}
 
-impl Model<JourneyPlatform> for JourneyPlatform {
-    type K = (i32, i32);
+    // Getters/Setters
+
+    pub fn journey_legacy_id(&self) -> i32 {
+        self.journey_legacy_id
+    }
+
+    pub fn administration(&self) -> &str {
+        &self.administration
+    }
 
-    fn id(&self) -> Self::K {
-        (self.journey_legacy_id, self.platform_id)
+    pub fn platform_id(&self) -> i32 {
+        self.platform_id
+    }
+
+    /// The time of the journey at the stop, used when the journey serves the stop several times.
+    pub fn time(&self) -> Option<NaiveTime> {
+        self.time
+    }
+
+    pub fn bit_field_id(&self) -> Option<i32> {
+        self.bit_field_id
     }
 }
 
@@ -909,18 +1714,38 @@ impl Line {
 
     // Getters/Setters
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn short_name(&self) -> &str {
+        &self.short_name
+    }
+
     pub fn set_short_name(&mut self, value: String) {
         self.short_name = value;
     }
 
+    pub fn long_name(&self) -> &str {
+        &self.long_name
+    }
+
     pub fn set_long_name(&mut self, value: String) {
         self.long_name = value;
     }
 
+    pub fn text_color(&self) -> &Color {
+        &self.text_color
+    }
+
     pub fn set_text_color(&mut self, value: Color) {
         self.text_color = value;
     }
 
+    pub fn background_color(&self) -> &Color {
+        &self.background_color
+    }
+
     pub fn set_background_color(&mut self, value: Color) {
         self.background_color = value;
     }
@@ -934,40 +1759,134 @@ impl Line {
 pub struct Platform {
     id: i32,
     name: String,
-    sectors: Option<String>,
+    sectors_designation: Option<String>,
+    sectors: Vec<PlatformSector>,
     stop_id: i32,
     sloid: String,
-    lv95_coordinates: Coordinates,
-    wgs84_coordinates: Coordinates,
+    lv95_coordinates: Option<Coordinates>,
+    wgs84_coordinates: Option<Coordinates>,
 }
 
 impl_Model!(Platform);
 
 impl Platform {
-    pub fn new(id: i32, name: String, sectors: Option<String>, stop_id: i32) -> Self {
+    pub fn new(id: i32, name: String, sectors_designation: Option<String>, stop_id: i32) -> Self {
         Self {
             id,
             name,
-            sectors,
+            sectors_designation,
+            sectors: Vec::new(),
             stop_id,
             sloid: String::default(),
-            lv95_coordinates: Coordinates::default(),
-            wgs84_coordinates: Coordinates::default(),
+            lv95_coordinates: None,
+            wgs84_coordinates: None,
         }
     }
 
     // Getters/Setters
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The sectors as written in the file, e.g. "AB" or "A-D".
+    pub fn sectors_designation(&self) -> Option<&str> {
+        self.sectors_designation.as_deref()
+    }
+
+    /// The sectors, in the order given by the file.
+    pub fn sectors(&self) -> &Vec<PlatformSector> {
+        &self.sectors
+    }
+
+    pub fn set_sectors(&mut self, value: Vec<PlatformSector>) {
+        self.sectors = value;
+    }
+
+    pub fn stop_id(&self) -> i32 {
+        self.stop_id
+    }
+
+    pub fn sloid(&self) -> &str {
+        &self.sloid
+    }
+
     pub fn set_sloid(&mut self, value: String) {
         self.sloid = value;
     }
 
+    /// Converted from the WGS84 coordinates if GLEISE_LV95 has no entry for the platform.
+    pub fn lv95_coordinates(&self) -> Option<Coordinates> {
+        self.lv95_coordinates
+            .or_else(|| self.wgs84_coordinates.map(|c| c.to_lv95()))
+    }
+
     pub fn set_lv95_coordinates(&mut self, value: Coordinates) {
-        self.lv95_coordinates = value;
+        self.lv95_coordinates = Some(value);
+    }
+
+    /// Converted from the LV95 coordinates if GLEISE_WGS has no entry for the platform.
+    pub fn wgs84_coordinates(&self) -> Option<Coordinates> {
+        self.wgs84_coordinates
+            .or_else(|| self.lv95_coordinates.map(|c| c.to_wgs84()))
     }
 
     pub fn set_wgs84_coordinates(&mut self, value: Coordinates) {
-        self.wgs84_coordinates = value;
+        self.wgs84_coordinates = Some(value);
+    }
+
+    // Functions
+
+    /// Appends sectors after the existing ones.
+    pub fn add_sectors(&mut self, names: Vec<String>) {
+        let index = self.sectors.len();
+        self.sectors.extend(
+            names
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| PlatformSector::new(name, index + i)),
+        );
+    }
+
+    /// Returns the boarding area (BHFART) of the stop containing the platform, found using the SLOIDs.
+    pub fn boarding_area<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a str> {
+        let sloid = Sloid::from_str(&self.sloid).ok()?;
+        data_storage
+            .stops()
+            .find(self.stop_id)?
+            .boarding_areas()
+            .iter()
+            .find(|boarding_area| {
+                Sloid::from_str(boarding_area).is_ok_and(|area| area.contains(&sloid))
+            })
+            .map(|boarding_area| boarding_area.as_str())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- PlatformSector
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PlatformSector {
+    name: String,
+    index: usize,
+}
+
+impl PlatformSector {
+    pub fn new(name: String, index: usize) -> Self {
+        Self { name, index }
+    }
+
+    // Getters/Setters
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The 0-based index of the sector in the sectors designation of the file.
+    pub fn index(&self) -> usize {
+        self.index
     }
 }
 
@@ -984,10 +1903,11 @@ pub struct Stop {
     synonyms: Option<Vec<String>>,
     lv95_coordinates: Option<Coordinates>,
     wgs84_coordinates: Option<Coordinates>,
+    altitude: Option<i16>,
     exchange_priority: i16,
     exchange_flag: i16,
     exchange_time: Option<(i16, i16)>, // (InterCity exchange time, Exchange time for all other journey types)
-    restrictions: i16,
+    restrictions: StopRestrictions,
     sloid: String,
     boarding_areas: Vec<String>,
 }
@@ -1010,10 +1930,11 @@ impl Stop {
             synonyms,
             lv95_coordinates: None,
             wgs84_coordinates: None,
+            altitude: None,
             exchange_priority: 8, // 8 is the default priority.
             exchange_flag: 0,
             exchange_time: None,
-            restrictions: 0,
+            restrictions: StopRestrictions::default(),
             sloid: String::default(),
             boarding_areas: Vec::new(),
         }
@@ -1025,22 +1946,51 @@ impl Stop {
         &self.name
     }
 
+    pub fn long_name(&self) -> Option<&str> {
+        self.long_name.as_deref()
+    }
+
+    pub fn abbreviation(&self) -> Option<&str> {
+        self.abbreviation.as_deref()
+    }
+
+    pub fn synonyms(&self) -> Option<&Vec<String>> {
+        self.synonyms.as_ref()
+    }
+
+    /// Converted from the WGS84 coordinates if BFKOORD_LV95 has no entry for the stop.
     pub fn lv95_coordinates(&self) -> Option<Coordinates> {
         self.lv95_coordinates
+            .or_else(|| self.wgs84_coordinates.map(|c| c.to_lv95()))
     }
 
     pub fn set_lv95_coordinates(&mut self, value: Coordinates) {
         self.lv95_coordinates = Some(value);
     }
 
+    /// Converted from the LV95 coordinates if BFKOORD_WGS has no entry for the stop.
     pub fn wgs84_coordinates(&self) -> Option<Coordinates> {
         self.wgs84_coordinates
+            .or_else(|| self.lv95_coordinates.map(|c| c.to_wgs84()))
     }
 
     pub fn set_wgs84_coordinates(&mut self, value: Coordinates) {
         self.wgs84_coordinates = Some(value);
     }
 
+    /// The altitude in meters above sea level, if provided by BFKOORD_LV95 or BFKOORD_WGS.
+    pub fn altitude(&self) -> Option<i16> {
+        self.altitude
+    }
+
+    pub fn set_altitude(&mut self, value: Option<i16>) {
+        self.altitude = value;
+    }
+
+    pub fn exchange_priority(&self) -> i16 {
+        self.exchange_priority
+    }
+
     pub fn set_exchange_priority(&mut self, value: i16) {
         self.exchange_priority = value;
     }
@@ -1061,14 +2011,26 @@ impl Stop {
         self.exchange_time = value;
     }
 
-    pub fn set_restrictions(&mut self, value: i16) {
+    pub fn restrictions(&self) -> StopRestrictions {
+        self.restrictions
+    }
+
+    pub fn set_restrictions(&mut self, value: StopRestrictions) {
         self.restrictions = value;
     }
 
+    pub fn sloid(&self) -> &str {
+        &self.sloid
+    }
+
     pub fn set_sloid(&mut self, value: String) {
         self.sloid = value;
     }
 
+    pub fn boarding_areas(&self) -> &Vec<String> {
+        &self.boarding_areas
+    }
+
     // Functions
 
     pub fn add_boarding_area(&mut self, value: String) {
@@ -1078,6 +2040,80 @@ impl Stop {
     pub fn can_be_used_as_exchange_point(&self) -> bool {
         self.exchange_flag() != 0
     }
+
+    /// Returns the border point of the stop (GRENZHLT), if the stop lies on a border.
+    pub fn border_point<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a BorderPoint> {
+        data_storage.border_points().find(self.id)
+    }
+
+    pub fn is_border_point(&self, data_storage: &DataStorage) -> bool {
+        self.border_point(data_storage).is_some()
+    }
+
+    /// Returns the stop group with the lowest ID the stop is a member of.
+    pub fn group<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a StopGroup> {
+        self.groups(data_storage).into_iter().next()
+    }
+
+    /// Returns the stop groups the stop is a member of, sorted by ID.
+    pub fn groups<'a>(&self, data_storage: &'a DataStorage) -> Vec<&'a StopGroup> {
+        data_storage
+            .stop_groups_by_stop_id()
+            .get(&self.id)
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| data_storage.stop_groups().find(id))
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- StopRestrictions
+// ------------------------------------------------------------------------------------------------
+
+/// The restrictions of a stop, stored as a set of bit flags.
+/// Unknown bits are kept as they are.
+///
+/// Source: HRDF 5.40.41 realisation guide, file BHFART (BHFART_60 before 2.0.7), row type B
+/// ("Einschränkungen"), columns 11-12. The value is the sum of the flags below.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct StopRestrictions(i16);
+
+impl StopRestrictions {
+    /// The stop must not be proposed by the stop search.
+    pub const NOT_SEARCHABLE: Self = Self(1);
+    /// The stop can be neither the origin nor the destination of a route.
+    pub const NOT_ORIGIN_OR_DESTINATION: Self = Self(2);
+    /// The stop cannot be used to change journeys along a route.
+    pub const NOT_VIA: Self = Self(4);
+
+    pub fn from_bits(bits: i16) -> Self {
+        Self(bits)
+    }
+
+    // Getters/Setters
+
+    pub fn bits(&self) -> i16 {
+        self.0
+    }
+
+    // Functions
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for StopRestrictions {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -1120,11 +2156,59 @@ impl StopConnection {
         self.duration
     }
 
+    pub fn attribute(&self) -> i32 {
+        self.attribute
+    }
+
     pub fn set_attribute(&mut self, value: i32) {
         self.attribute = value;
     }
 }
 
+// ------------------------------------------------------------------------------------------------
+// --- StopGroup
+// ------------------------------------------------------------------------------------------------
+
+/// A meta-stop grouping several stops (METABHF rows containing a ":"), e.g. a station and its tram stops.
+/// The ID is the number of the meta-stop, which is not necessarily listed in BAHNHOF.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StopGroup {
+    id: i32,
+    stop_ids: Vec<i32>,
+}
+
+impl_Model!(StopGroup);
+
+impl StopGroup {
+    pub fn new(id: i32, stop_ids: Vec<i32>) -> Self {
+        Self { id, stop_ids }
+    }
+
+    // Getters/Setters
+
+    pub fn stop_ids(&self) -> &Vec<i32> {
+        &self.stop_ids
+    }
+
+    // Functions
+
+    pub fn add_stop_ids(&mut self, values: Vec<i32>) {
+        self.stop_ids.extend(values);
+    }
+
+    /// Returns the stop of the meta-stop itself, if it is listed in BAHNHOF.
+    pub fn stop<'a>(&self, data_storage: &'a DataStorage) -> Option<&'a Stop> {
+        data_storage.stops().find(self.id)
+    }
+
+    pub fn members<'a>(&self, data_storage: &'a DataStorage) -> Vec<&'a Stop> {
+        self.stop_ids
+            .iter()
+            .filter_map(|&stop_id| data_storage.stops().find(stop_id))
+            .collect()
+    }
+}
+
 // ------------------------------------------------------------------------------------------------
 // --- ThroughService
 // ------------------------------------------------------------------------------------------------
@@ -1160,6 +2244,8 @@ impl ThroughService {
         }
     }
 
+    // Getters/Setters
+
     pub fn journey_1_id(&self) -> &JourneyId {
         &self.journey_1_id
     }
@@ -1244,17 +2330,33 @@ impl TransportCompany {
 
     // Getters/Setters
 
+    pub fn short_name(&self, language: Language) -> Option<&str> {
+        self.short_name.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_short_name(&mut self, language: Language, value: &str) {
         self.short_name.insert(language, value.to_string());
     }
 
+    pub fn long_name(&self, language: Language) -> Option<&str> {
+        self.long_name.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_long_name(&mut self, language: Language, value: &str) {
         self.long_name.insert(language, value.to_string());
     }
 
+    pub fn full_name(&self, language: Language) -> Option<&str> {
+        self.full_name.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_full_name(&mut self, language: Language, value: &str) {
         self.full_name.insert(language, value.to_string());
     }
+
+    pub fn administrations(&self) -> &Vec<String> {
+        &self.administrations
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -1313,13 +2415,49 @@ impl TransportType {
         self.product_class_id
     }
 
+    pub fn tariff_group(&self) -> &str {
+        &self.tarrif_group
+    }
+
+    pub fn output_control(&self) -> i16 {
+        self.output_control
+    }
+
+    pub fn short_name(&self) -> &str {
+        &self.short_name
+    }
+
+    pub fn surcharge(&self) -> i16 {
+        self.surchage
+    }
+
+    pub fn flag(&self) -> &str {
+        &self.flag
+    }
+
+    pub fn product_class_name(&self, language: Language) -> Option<&str> {
+        self.product_class_name.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_product_class_name(&mut self, language: Language, value: &str) {
         self.product_class_name.insert(language, value.to_string());
     }
 
+    pub fn category_name(&self, language: Language) -> Option<&str> {
+        self.category_name.get(&language).map(|s| s.as_str())
+    }
+
     pub fn set_category_name(&mut self, language: Language, value: &str) {
         self.category_name.insert(language, value.to_string());
     }
+
+    // Functions
+
+    /// Returns true for the long-distance products, i.e. the product classes 0 (e.g. ICE, TGV), 1 (e.g. EC, IC) and 2 (e.g. IR).
+    /// The InterCity exchange time of UMSTEIGB applies between two of them.
+    pub fn is_inter_city(&self) -> bool {
+        self.product_class_id <= 2
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -1334,3 +2472,217 @@ pub enum Version {
     V_5_40_41_2_0_6,
     V_5_40_41_2_0_7,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    // The approximate formulas are accurate to about 1 meter, i.e. about 0.00001° of latitude.
+    const DEGREE_TOLERANCE: f64 = 0.00001;
+    const METER_TOLERANCE: f64 = 1.0;
+
+    fn assert_wgs84_eq(expected: (f64, f64), coordinates: Coordinates) {
+        let (latitude, longitude) = (
+            coordinates.latitude().unwrap(),
+            coordinates.longitude().unwrap(),
+        );
+        assert!(
+            (latitude - expected.0).abs() < DEGREE_TOLERANCE
+                && (longitude - expected.1).abs() < DEGREE_TOLERANCE,
+            "expected {expected:?}, got ({latitude}, {longitude})"
+        );
+    }
+
+    fn assert_lv95_eq(expected: (f64, f64), coordinates: Coordinates) {
+        let (easting, northing) = (
+            coordinates.easting().unwrap(),
+            coordinates.northing().unwrap(),
+        );
+        assert!(
+            (easting - expected.0).abs() < METER_TOLERANCE
+                && (northing - expected.1).abs() < METER_TOLERANCE,
+            "expected {expected:?}, got ({easting}, {northing})"
+        );
+    }
+
+    #[test]
+    fn lv95_to_wgs84() {
+        // The origin of LV95 in Bern, i.e. 46° 57' 03.89" N, 7° 26' 19.09" E.
+        let bern = Coordinates::new(CoordinateSystem::LV95, 2_600_000.0, 1_200_000.0);
+        assert_wgs84_eq((46.951081, 7.438637), bern.to_wgs84());
+
+        // The example of the swisstopo documentation, i.e. 46° 02' 38.87" N, 8° 43' 49.79" E.
+        let coordinates = Coordinates::new(CoordinateSystem::LV95, 2_700_000.0, 1_100_000.0);
+        assert_wgs84_eq((46.044131, 8.730497), coordinates.to_wgs84());
+    }
+
+    #[test]
+    fn wgs84_to_lv95() {
+        let coordinates = Coordinates::new(CoordinateSystem::WGS84, 46.044131, 8.730497);
+        assert_lv95_eq((2_700_000.0, 1_100_000.0), coordinates.to_lv95());
+    }
+
+    #[test]
+    fn round_trip() {
+        let bern = Coordinates::new(CoordinateSystem::LV95, 2_600_000.0, 1_200_000.0);
+        assert_lv95_eq((2_600_000.0, 1_200_000.0), bern.to_wgs84().to_lv95());
+    }
+
+    #[test]
+    fn border_crossings() {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+
+        // Beta (8500002) and Delta (8500004) are border points.
+        assert_eq!(vec![1], journey(1).border_crossings(data_storage));
+        assert!(journey(1).crosses_border(data_storage));
+        assert_eq!(
+            vec!["Beta"],
+            journey(1)
+                .border_stops(data_storage)
+                .iter()
+                .map(|stop| stop.name())
+                .collect::<Vec<_>>()
+        );
+        // The journey 11 starts at Beta and ends at Delta, it does not cross any border.
+        assert!(journey(11).border_crossings(data_storage).is_empty());
+        assert!(!journey(11).crosses_border(data_storage));
+        // The journey 12 stops at Gamma between Beta and Delta.
+        assert!(journey(12).border_crossings(data_storage).is_empty());
+    }
+
+    #[test]
+    fn border_sections() {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+
+        assert_eq!(
+            vec![(0, 1), (1, 2)],
+            journey(1).border_sections(data_storage)
+        );
+        // Alpha, Beta, Gamma and Delta.
+        assert_eq!(
+            vec![(0, 1), (1, 3)],
+            journey(8).border_sections(data_storage)
+        );
+        assert_eq!(vec![(0, 1)], journey(11).border_sections(data_storage));
+    }
+
+    #[test]
+    fn attributes_in_section() {
+        let data_storage = fixture_data_storage();
+        let journey = data_storage.journeys().find(1).unwrap();
+        let designations = |start, end| -> Vec<&str> {
+            journey
+                .attributes_in_section(data_storage, start, end)
+                .into_iter()
+                .map(|attribute| attribute.designation())
+                .collect()
+        };
+
+        // WR only runs from Beta: it is shown in its partial route form (WP) on the whole trip.
+        // AU only applies when boarding at Beta or later (stop scope 1).
+        assert_eq!(vec!["VR", "WP"], designations(0, 2));
+        assert_eq!(vec!["VR", "WR", "AU"], designations(1, 2));
+        assert_eq!(vec!["VR"], designations(0, 1));
+    }
+
+    #[test]
+    fn render_attributes() {
+        let data_storage = fixture_data_storage();
+        let journey = data_storage.journeys().find(1).unwrap();
+
+        assert_eq!(
+            vec![("VR", "Reservation"), ("WR", "Restaurant"), ("AU", "AU")],
+            journey.render_attributes(data_storage, 1, 2, Language::English)
+        );
+        // The designation is used when there is no description in the language.
+        assert_eq!(
+            vec![("VR", "VR"), ("WP", "Speisewagen auf Teilstrecke")],
+            journey.render_attributes(data_storage, 0, 2, Language::German)
+        );
+        assert_eq!(
+            vec![("VR", "Reservation"), ("WP", "WP")],
+            journey.render_attributes(data_storage, 0, 2, Language::English)
+        );
+    }
+
+    #[test]
+    fn information_texts() {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+
+        assert_eq!(
+            Some("ch:1:sjyid:100001:1-001"),
+            journey(1).sjyid(data_storage)
+        );
+        assert_eq!(Some("Zürich Nord"), journey(1).region(data_storage));
+        // The journey 1 has no ZN information text.
+        assert_eq!(None, journey(1).train_name(data_storage));
+        // The XX information text has an unknown code, it is not a hint.
+        assert_eq!(
+            vec!["Hinweis EN"],
+            journey(1).hints(data_storage, Language::English)
+        );
+        assert_eq!(
+            vec!["Hinweis IT"],
+            journey(1).hints(data_storage, Language::Italian)
+        );
+
+        assert_eq!(None, journey(2).sjyid(data_storage));
+        assert_eq!(None, journey(2).region(data_storage));
+        assert!(journey(2).hints(data_storage, Language::German).is_empty());
+    }
+
+    #[test]
+    fn direction_text_at() {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+
+        assert_eq!(
+            Some("Dorf"),
+            journey(1).direction_text_at(data_storage, 8500002)
+        );
+        // The journey 2 has no *R row, the name of its last stop is used.
+        assert_eq!(
+            Some("Delta"),
+            journey(2).direction_text_at(data_storage, 8500003)
+        );
+        // The journey 4 has a *R H row without direction code.
+        assert_eq!(
+            Some("Delta"),
+            journey(4).direction_text_at(data_storage, 8500001)
+        );
+        assert_eq!(None, journey(4).direction_text_at(data_storage, 8500002));
+        // The journey 14 serves Alpha twice, the first visit is used.
+        assert_eq!(
+            Some("Beta"),
+            journey(14).direction_text_at(data_storage, 8500001)
+        );
+    }
+
+    #[test]
+    fn direction_text_at_index() {
+        let data_storage = fixture_data_storage();
+        let journey = data_storage.journeys().find(14).unwrap();
+        let direction_texts: Vec<_> = (0..=5)
+            .map(|route_index| journey.direction_text_at_index(data_storage, route_index))
+            .collect();
+
+        // The journey 14 has a bare *R H row from Alpha to Beta, then the direction R000001 from Beta to Gamma.
+        // At Beta, which belongs to both sections, the direction with a code wins.
+        assert_eq!(
+            vec![
+                Some("Beta"),
+                Some("Dorf"),
+                Some("Dorf"),
+                Some("Dorf"),
+                None,
+                None
+            ],
+            direction_texts
+        );
+    }
+}
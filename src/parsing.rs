@@ -1,5 +1,6 @@
 mod attribute_parser;
 mod bit_field_parser;
+mod border_point_parser;
 mod direction_parser;
 mod exchange_administration_parser;
 mod exchange_journey_parser;
@@ -18,6 +19,7 @@ mod transport_type_parser;
 
 pub use attribute_parser::parse as load_attributes;
 pub use bit_field_parser::parse as load_bit_fields;
+pub use border_point_parser::parse as load_border_points;
 pub use direction_parser::parse as load_directions;
 pub use exchange_administration_parser::parse as load_exchange_times_administration;
 pub use exchange_journey_parser::parse as load_exchange_times_journey;
@@ -351,7 +353,7 @@ impl FileParser {
         Ok(lines)
     }
 
-    pub fn parse(&self) -> ParsedRowIterator {
+    pub fn parse(&self) -> ParsedRowIterator<'_> {
         ParsedRowIterator {
             rows_iter: self.rows.iter(),
             row_parser: &self.row_parser,
@@ -17,8 +17,6 @@
 ///
 /// ## Description of how the offers can be displayed
 ///
-/// **Important:** Currently these lines are not used in the library
-///
 /// ### Example (excerpt):
 ///
 /// `
@@ -87,11 +85,15 @@ fn attribute_row_parser(version: Version) -> Result<RowParser> {
                 ColumnDefinition::new(10, 11, ExpectedType::Integer16),
             ],
         ),
-        // This row is ignored.
+        // This row contains the designations to output for a partial route and for a full route.
         RowDefinition::new(
             RowType::RowB as i32,
             Box::new(FastRowMatcher::new(1, 1, "#", true)),
-            vec![ColumnDefinition::new(1, -1, ExpectedType::String)],
+            vec![
+                ColumnDefinition::new(3, 4, ExpectedType::String),
+                ColumnDefinition::new(6, 7, ExpectedType::String),
+                ColumnDefinition::new(9, 10, ExpectedType::String),
+            ],
         ),
         // This row indicates the language for translations in the section that follows it.
         RowDefinition::new(
@@ -133,7 +135,7 @@ fn attribute_row_converter(parser: FileParser) -> Result<FxHashMapsAndTypeConver
             let attribute = create_instance(values, &auto_increment, &mut pk_type_converter);
             data.insert(attribute.id(), attribute);
         } else if id == RowType::RowB as i32 {
-            // We discard lines starting with #
+            set_output_designations(values, &pk_type_converter, &mut data)?;
         } else if id == RowType::RowC as i32 {
             update_current_language(values, &mut current_language)?;
         } else if id == RowType::RowD as i32 {
@@ -195,6 +197,30 @@ fn create_instance(
     )
 }
 
+fn row_b_from_parsed_values(mut values: Vec<ParsedValue>) -> (String, String, String) {
+    let legacy_id: String = values.remove(0).into();
+    let partial_route_designation: String = values.remove(0).into();
+    let full_route_designation: String = values.remove(0).into();
+    (legacy_id, partial_route_designation, full_route_designation)
+}
+
+fn set_output_designations(
+    values: Vec<ParsedValue>,
+    pk_type_converter: &FxHashMap<String, i32>,
+    data: &mut FxHashMap<i32, Attribute>,
+) -> Result<()> {
+    let (legacy_id, partial_route_designation, full_route_designation) =
+        row_b_from_parsed_values(values);
+    let id = pk_type_converter
+        .get(&legacy_id)
+        .ok_or(ErrorKind::UnknownLegacyId)?;
+    let attribute = data.get_mut(id).ok_or(ErrorKind::UnknownId)?;
+    attribute.set_partial_route_designation(partial_route_designation);
+    attribute.set_full_route_designation(full_route_designation);
+
+    Ok(())
+}
+
 fn row_d_from_parsed_values(mut values: Vec<ParsedValue>) -> (String, String) {
     let legacy_id: String = values.remove(0).into();
     let description: String = values.remove(0).into();
@@ -325,6 +351,7 @@ mod tests {
     fn type_converter_row_a_v207() {
         let rows = vec![
             "GK 0   4  5".to_string(),
+            "# GK GK GK".to_string(),
             "<deu>".to_string(),
             "GK  Zollkontrolle möglich, mehr Zeit einrechnen".to_string(),
             "<fra>".to_string(),
@@ -353,7 +380,9 @@ mod tests {
                     "English":"Possible customs check, please allow extra time",
                     "French":"Contrôle douanier possible, prévoir davantage de temps",
                     "Italian":"Possibile controllo doganale, prevedere più tempo"
-                }
+                },
+                "partial_route_designation":"GK",
+                "full_route_designation":"GK"
             }"#;
         let (attribute, reference) = get_json_values(attribute, reference).unwrap();
         assert_eq!(attribute, reference);
@@ -361,16 +390,26 @@ mod tests {
 
     #[test]
     fn parser_row_b_v207() {
-        let rows = vec!["# PG PG PG".to_string()];
+        let rows = vec!["# PG PG PG".to_string(), "# 2  2  Y".to_string()];
         let parser = FileParser {
             row_parser: attribute_row_parser(Version::V_5_40_41_2_0_7).unwrap(),
             rows,
         };
         let mut parser_iterator = parser.parse();
-        let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+        let (id, _, parsed_values) = parser_iterator.next().unwrap().unwrap();
         assert_eq!(id, RowType::RowB as i32);
-        let description: String = parsed_values.remove(0).into();
-        assert_eq!(&description, "# PG PG PG");
+        let (legacy_id, partial_route_designation, full_route_designation) =
+            row_b_from_parsed_values(parsed_values);
+        assert_eq!("PG", &legacy_id);
+        assert_eq!("PG", &partial_route_designation);
+        assert_eq!("PG", &full_route_designation);
+        let (id, _, parsed_values) = parser_iterator.next().unwrap().unwrap();
+        assert_eq!(id, RowType::RowB as i32);
+        let (legacy_id, partial_route_designation, full_route_designation) =
+            row_b_from_parsed_values(parsed_values);
+        assert_eq!("2", &legacy_id);
+        assert_eq!("2", &partial_route_designation);
+        assert_eq!("Y", &full_route_designation);
     }
 
     #[test]
@@ -0,0 +1,152 @@
+/// # Border point parsing
+///
+/// For more informations see
+/// [https://opentransportdata.swiss/en/cookbook/hafas-rohdaten-format-hrdf/#Technical_description_What_is_in_the_HRDF_files_contents](the HRDF documentation).
+///
+/// List of the border points, i.e. the stops where a journey crosses a (tariff) border, e.g. a national border.
+///
+/// `8500010 Basel SBB    % Grenzpunkt Basel SBB`
+///
+/// The stop number is followed by an optional description. Comments (after "%") are ignored.
+///
+/// Can be read in decoupled from other data.
+///
+/// 1 file(s).
+/// File(s) read by the parser:
+/// GRENZHLT
+use rustc_hash::FxHashMap;
+
+use crate::{
+    Result,
+    models::{BorderPoint, Model},
+    parsing::{
+        AdvancedRowMatcher, ColumnDefinition, ExpectedType, FastRowMatcher, FileParser,
+        ParsedValue, RowDefinition, RowParser,
+    },
+    storage::ResourceStorage,
+};
+
+const ROW_A: i32 = 1;
+const ROW_B: i32 = 2;
+
+fn border_point_row_parser() -> Result<RowParser> {
+    #[rustfmt::skip]
+    let row_parser = RowParser::new(vec![
+        // This row is used to create a BorderPoint instance with a description.
+        RowDefinition::new(ROW_A, Box::new(AdvancedRowMatcher::new(r"^[0-9]{7} ")?), vec![
+            ColumnDefinition::new(1, 7, ExpectedType::Integer32),
+            ColumnDefinition::new(9, -1, ExpectedType::String),
+        ]),
+        // This row is used to create a BorderPoint instance without a description.
+        RowDefinition::new(ROW_B, Box::new(FastRowMatcher::new(1, 0, "", true)), vec![
+            ColumnDefinition::new(1, 7, ExpectedType::Integer32),
+        ]),
+    ]);
+    Ok(row_parser)
+}
+
+fn border_point_row_converter(parser: FileParser) -> Result<FxHashMap<i32, BorderPoint>> {
+    let data = parser
+        .parse()
+        .map(|x| x.map(|(_, _, values)| create_instance(values)))
+        .collect::<Result<Vec<_>>>()?;
+    let data = BorderPoint::vec_to_map(data);
+    Ok(data)
+}
+
+pub fn parse(path: &str) -> Result<ResourceStorage<BorderPoint>> {
+    log::info!("Parsing GRENZHLT...");
+    let row_parser = border_point_row_parser()?;
+    let parser = FileParser::new(&format!("{path}/GRENZHLT"), row_parser)?;
+    let data = border_point_row_converter(parser)?;
+
+    Ok(ResourceStorage::new(data))
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Data Processing Functions
+// ------------------------------------------------------------------------------------------------
+
+fn create_instance(mut values: Vec<ParsedValue>) -> BorderPoint {
+    let stop_id: i32 = values.remove(0).into();
+    let description = if values.is_empty() {
+        None
+    } else {
+        let description: String = values.remove(0).into();
+        // Everything after the "%" is a comment.
+        description
+            .split('%')
+            .next()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+
+    BorderPoint::new(stop_id, description)
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use crate::parsing::tests::get_json_values;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn row_parser_v207() {
+        let rows = vec![
+            "8500010 Basel SBB    % Grenzpunkt Basel SBB".to_string(),
+            "8501120".to_string(),
+        ];
+        let parser = FileParser {
+            row_parser: border_point_row_parser().unwrap(),
+            rows,
+        };
+        let mut parser_iterator = parser.parse();
+        {
+            let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+            assert_eq!(ROW_A, id);
+            let stop_id: i32 = parsed_values.remove(0).into();
+            assert_eq!(8500010, stop_id);
+            let description: String = parsed_values.remove(0).into();
+            assert_eq!("Basel SBB    % Grenzpunkt Basel SBB", &description);
+        }
+        {
+            let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+            assert_eq!(ROW_B, id);
+            let stop_id: i32 = parsed_values.remove(0).into();
+            assert_eq!(8501120, stop_id);
+        }
+    }
+
+    #[test]
+    fn type_converter_v207() {
+        let rows = vec![
+            "8500010 Basel SBB    % Grenzpunkt Basel SBB".to_string(),
+            "8501120".to_string(),
+        ];
+        let parser = FileParser {
+            row_parser: border_point_row_parser().unwrap(),
+            rows,
+        };
+        let data = border_point_row_converter(parser).unwrap();
+        // First row (id: 8500010)
+        let attribute = data.get(&8500010).unwrap();
+        let reference = r#"
+            {
+                "id": 8500010,
+                "description": "Basel SBB"
+            }"#;
+        let (attribute, reference) = get_json_values(attribute, reference).unwrap();
+        assert_eq!(attribute, reference);
+        // Second row (id: 8501120)
+        let attribute = data.get(&8501120).unwrap();
+        let reference = r#"
+            {
+                "id": 8501120,
+                "description": null
+            }"#;
+        let (attribute, reference) = get_json_values(attribute, reference).unwrap();
+        assert_eq!(attribute, reference);
+    }
+}
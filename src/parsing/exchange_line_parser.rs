@@ -353,13 +353,13 @@ mod tests {
                     "administration": "sbg034",
                     "transport_type_id": 2,
                     "line_id": "7339",
-                    "direction": "Return"
+                    "direction": "Outbound"
                  },
                  "line_2": {
                     "administration": "sbg034",
                     "transport_type_id": 3,
                     "line_id": "7341",
-                    "direction": "Return"
+                    "direction": "Outbound"
                  },
                  "duration": 0,
                  "is_guaranteed": true
@@ -539,9 +539,8 @@ fn add_attribute(
 
 // Parsing RowE
 
-fn row_e_from_parsed_values(
-    mut values: Vec<ParsedValue>,
-) -> (
+// (code, from_stop_id, until_stop_id, bit_field_id, information_text_id, departure_time, arrival_time)
+type RowE = (
     String,
     Option<i32>,
     Option<i32>,
@@ -549,7 +548,9 @@ fn row_e_from_parsed_values(
     i32,
     Option<i32>,
     Option<i32>,
-) {
+);
+
+fn row_e_from_parsed_values(mut values: Vec<ParsedValue>) -> RowE {
     let code: String = values.remove(0).into();
     let from_stop_id: Option<i32> = values.remove(0).into();
     let until_stop_id: Option<i32> = values.remove(0).into();
@@ -762,14 +763,15 @@ fn row_i_from_parsed_values(mut values: Vec<ParsedValue>) -> (i32, Option<i32>,
 
 fn add_route_entry(values: Vec<ParsedValue>, journey: &mut Journey) {
     let (stop_id, arrival_time, departure_time) = row_i_from_parsed_values(values);
-    let arrival_time = create_time(arrival_time);
-    let departure_time = create_time(departure_time);
 
-    journey.add_route_entry(JourneyRouteEntry::new(
+    let mut route_entry = JourneyRouteEntry::new(
         stop_id,
-        arrival_time,
-        departure_time,
-    ));
+        create_time(arrival_time),
+        create_time(departure_time),
+    );
+    route_entry.set_alighting_allowed(arrival_time.is_none_or(|time| time >= 0));
+    route_entry.set_boarding_allowed(departure_time.is_none_or(|time| time >= 0));
+    journey.add_route_entry(route_entry);
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -930,6 +932,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn add_route_entry_negative_times_v207() {
+        let rows = vec![
+            "8500010 Basel SBB             00748  00806                 %".to_string(),
+            "0000175 Hauenstein-Basistunn -00833 -00833                 %".to_string(),
+            "8503000 Zürich HB            -00900                        %".to_string(),
+        ];
+        let parser = FileParser {
+            row_parser: journey_row_parser(),
+            rows,
+        };
+        let mut journey = Journey::new(1, 3, "000011".to_string());
+        for row in parser.parse() {
+            let (_, _, parsed_values) = row.unwrap();
+            add_route_entry(parsed_values, &mut journey);
+        }
+
+        let route = journey.route();
+        assert!(route[0].alighting_allowed());
+        assert!(route[0].boarding_allowed());
+        assert!(!route[1].alighting_allowed());
+        assert!(!route[1].boarding_allowed());
+        assert_eq!(
+            Some(NaiveTime::from_hms_opt(8, 33, 0).unwrap()),
+            *route[1].arrival_time()
+        );
+        assert!(!route[2].alighting_allowed());
+        assert!(route[2].boarding_allowed());
+    }
+
     #[test]
     fn parsing_rows_alt_v207() {
         let rows = vec![
@@ -68,7 +68,7 @@ fn construct_row_parser(version: Version) -> RowParser {
                     vec![
                         ColumnDefinition::new(1, 7, ExpectedType::Integer32),
                         ColumnDefinition::new(10, 16, ExpectedType::Integer32), // Should be 9-16, but here the # character is ignored.
-                        ColumnDefinition::new(20, -1, ExpectedType::String),
+                        ColumnDefinition::new(22, -1, ExpectedType::String),
                     ],
                 ),
                 // This row is used to set the coordinates (either lv95 either wgs84)
@@ -147,6 +147,7 @@ pub fn parse(
 
     let mut bytes_offset = 0;
     let mut journey_platform = Vec::new();
+    let mut sections = Vec::new();
 
     match version {
         Version::V_5_40_41_2_0_4 | Version::V_5_40_41_2_0_5 | Version::V_5_40_41_2_0_6 => {
@@ -185,10 +186,7 @@ pub fn parse(
                             &mut platforms_pk_type_converter,
                         )?);
                     }
-                    ROW_SECTION => {
-                        // We do nothing
-                        // We may want to use section at some point
-                    }
+                    ROW_SECTION => sections.push(values),
                     ROW_SLOID | ROW_COORD => {
                         // We do nothing, coordinates and sloid are parsed afterwards
                     }
@@ -200,11 +198,17 @@ pub fn parse(
 
     let mut platforms = Platform::vec_to_map(platforms);
 
+    sections.into_iter().try_for_each(|values| {
+        platform_add_sectors(values, &platforms_pk_type_converter, &mut platforms)
+    })?;
+
+    let journey_platform_auto_increment = AutoIncrement::new();
     let journey_platform = journey_platform
         .into_iter()
         .map(|values| {
             create_journey_platform(
                 values,
+                &journey_platform_auto_increment,
                 journeys_pk_type_converter,
                 &platforms_pk_type_converter,
             )
@@ -301,6 +305,7 @@ fn load_coordinates_for_platforms(
 
 fn create_journey_platform(
     mut values: Vec<ParsedValue>,
+    auto_increment: &AutoIncrement,
     journeys_pk_type_converter: &FxHashSet<JourneyId>,
     platforms_pk_type_converter: &FxHashMap<(i32, i32), i32>,
 ) -> Result<JourneyPlatform> {
@@ -322,6 +327,7 @@ fn create_journey_platform(
     let time = time.map(|x| create_time_from_value(x as u32));
 
     Ok(JourneyPlatform::new(
+        auto_increment.next(),
         journey_id,
         administration,
         platform_id,
@@ -340,7 +346,7 @@ fn create_platform(
     let platform_data: String = values.remove(0).into();
 
     let id = auto_increment.next();
-    let (code, sectors) = parse_platform_data(platform_data)?;
+    let (code, sectors_designation) = parse_platform_data(platform_data)?;
 
     if let Some(previous) = platforms_pk_type_converter.insert((stop_id, index), id) {
         log::warn!(
@@ -348,7 +354,33 @@ fn create_platform(
         );
     };
 
-    Ok(Platform::new(id, code, sectors, stop_id))
+    let mut platform = Platform::new(id, code, sectors_designation.clone(), stop_id);
+    if let Some(sectors_designation) = sectors_designation {
+        platform.add_sectors(parse_sectors(&sectors_designation));
+    }
+
+    Ok(platform)
+}
+
+fn platform_add_sectors(
+    mut values: Vec<ParsedValue>,
+    pk_type_converter: &FxHashMap<(i32, i32), i32>,
+    data: &mut FxHashMap<i32, Platform>,
+) -> Result<()> {
+    let stop_id: i32 = values.remove(0).into();
+    let index: i32 = values.remove(0).into();
+    let section_data: String = values.remove(0).into();
+
+    let id = pk_type_converter
+        .get(&(stop_id, index))
+        .ok_or(ErrorKind::UnknownLegacyPlatformId)?;
+    let platform = data.get_mut(id).ok_or(ErrorKind::UnknownId)?;
+
+    if let Some(sectors_designation) = parse_entries(section_data).get("A") {
+        platform.add_sectors(parse_sectors(sectors_designation));
+    }
+
+    Ok(())
 }
 
 fn platform_set_sloid(
@@ -415,16 +447,22 @@ fn platform_set_coordinates(
 // --- Helper Functions
 // ------------------------------------------------------------------------------------------------
 
-fn parse_platform_data(mut platform_data: String) -> Result<(String, Option<String>)> {
-    platform_data = format!("{} ", platform_data);
-    let data = platform_data.split("' ").filter(|&s| !s.is_empty()).fold(
-        FxHashMap::default(),
-        |mut acc, item| {
+/// Parses the entries of the form X 'value' into a map.
+fn parse_entries(mut data: String) -> FxHashMap<String, String> {
+    data = format!("{} ", data);
+    data.split("' ")
+        .filter(|&s| !s.is_empty())
+        .fold(FxHashMap::default(), |mut acc, item| {
             let parts: Vec<&str> = item.split(" '").collect();
-            acc.insert(parts[0], parts[1]);
+            if let [key, value] = parts.as_slice() {
+                acc.insert(key.trim().to_owned(), value.to_string());
+            }
             acc
-        },
-    );
+        })
+}
+
+fn parse_platform_data(platform_data: String) -> Result<(String, Option<String>)> {
+    let data = parse_entries(platform_data);
 
     // There should always be a G entry.
     let code = data
@@ -435,3 +473,55 @@ fn parse_platform_data(mut platform_data: String) -> Result<(String, Option<Stri
 
     Ok((code, sectors))
 }
+
+/// Splits the sectors designation into sector names, e.g. "AB" and "A-B" both become ["A", "B"].
+/// Tokens containing digits (e.g. "D1") are kept as they are.
+fn parse_sectors(sectors_designation: &str) -> Vec<String> {
+    sectors_designation
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .flat_map(|token| {
+            let chars: Vec<char> = token.chars().collect();
+            match chars.as_slice() {
+                [start, '-', end]
+                    if start.is_alphabetic() && end.is_alphabetic() && start <= end =>
+                {
+                    (*start..=*end).map(String::from).collect()
+                }
+                _ if chars.iter().all(|c| c.is_alphabetic()) => {
+                    chars.into_iter().map(String::from).collect()
+                }
+                _ => vec![token.to_owned()],
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_sectors_letters() {
+        assert_eq!(vec!["A", "B"], parse_sectors("AB"));
+    }
+
+    #[test]
+    fn parse_sectors_range() {
+        assert_eq!(vec!["A", "B", "C", "D"], parse_sectors("A-D"));
+    }
+
+    #[test]
+    fn parse_sectors_single_letter() {
+        assert_eq!(vec!["C"], parse_sectors("C"));
+    }
+
+    #[test]
+    fn parse_sectors_invalid() {
+        // Unexpected tokens are kept as they are.
+        assert_eq!(vec!["D-A"], parse_sectors("D-A"));
+        assert_eq!(vec!["D1"], parse_sectors("D1"));
+        assert!(parse_sectors(" ").is_empty());
+    }
+}
@@ -7,7 +7,7 @@ use rustc_hash::FxHashMap;
 use crate::{
     Result,
     error::ErrorKind,
-    models::{Model, StopConnection},
+    models::{Model, StopConnection, StopGroup},
     parsing::{
         AdvancedRowMatcher, ColumnDefinition, ExpectedType, FastRowMatcher, FileParser,
         ParsedValue, RowDefinition, RowParser,
@@ -16,15 +16,11 @@ use crate::{
     utils::AutoIncrement,
 };
 
-pub fn parse(
-    path: &str,
-    attributes_pk_type_converter: &FxHashMap<String, i32>,
-) -> Result<ResourceStorage<StopConnection>> {
-    log::info!("Parsing METABHF...");
-    const ROW_A: i32 = 1;
-    const ROW_B: i32 = 2;
-    const ROW_C: i32 = 3;
+const ROW_A: i32 = 1;
+const ROW_B: i32 = 2;
+const ROW_C: i32 = 3;
 
+fn stop_connection_row_parser() -> Result<RowParser> {
     #[rustfmt::skip]
     let row_parser = RowParser::new(vec![
         // This row is used to create a StopConnection instance.
@@ -37,24 +33,33 @@ pub fn parse(
         RowDefinition::new(ROW_B, Box::new(FastRowMatcher::new(1, 2, "*A", true)), vec![
             ColumnDefinition::new(4, 5, ExpectedType::String),
         ]),
-        // This row is ignored.
-        RowDefinition::new(ROW_C, Box::new(FastRowMatcher::new(8, 1, ":", true)), Vec::new()),
+        // This row is used to create a StopGroup instance.
+        RowDefinition::new(ROW_C, Box::new(FastRowMatcher::new(8, 1, ":", true)), vec![
+            ColumnDefinition::new(1, 7, ExpectedType::Integer32),
+            ColumnDefinition::new(10, -1, ExpectedType::String),
+        ]),
     ]);
-    let parser = FileParser::new(&format!("{path}/METABHF"), row_parser)?;
+    Ok(row_parser)
+}
 
+fn stop_connection_row_converter(
+    parser: FileParser,
+    attributes_pk_type_converter: &FxHashMap<String, i32>,
+) -> Result<(FxHashMap<i32, StopConnection>, FxHashMap<i32, StopGroup>)> {
     let auto_increment = AutoIncrement::new();
     let mut data = Vec::new();
+    let mut stop_groups = FxHashMap::default();
 
     for x in parser.parse() {
         let (id, _, values) = x?;
         match id {
             ROW_A => data.push(create_instance(values, &auto_increment)),
+            ROW_C => add_stop_group(values, &mut stop_groups)?,
             _ => {
                 let stop_connection = data.last_mut().ok_or(ErrorKind::RowMissing { typ: "A" })?;
 
                 match id {
                     ROW_B => set_attribute(values, stop_connection, attributes_pk_type_converter)?,
-                    ROW_C => {}
                     _ => unreachable!(),
                 }
             }
@@ -62,8 +67,22 @@ pub fn parse(
     }
 
     let data = StopConnection::vec_to_map(data);
+    Ok((data, stop_groups))
+}
 
-    Ok(ResourceStorage::new(data))
+pub fn parse(
+    path: &str,
+    attributes_pk_type_converter: &FxHashMap<String, i32>,
+) -> Result<(ResourceStorage<StopConnection>, ResourceStorage<StopGroup>)> {
+    log::info!("Parsing METABHF...");
+    let row_parser = stop_connection_row_parser()?;
+    let parser = FileParser::new(&format!("{path}/METABHF"), row_parser)?;
+    let (data, stop_groups) = stop_connection_row_converter(parser, attributes_pk_type_converter)?;
+
+    Ok((
+        ResourceStorage::new(data),
+        ResourceStorage::new(stop_groups),
+    ))
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -78,6 +97,26 @@ fn create_instance(mut values: Vec<ParsedValue>, auto_increment: &AutoIncrement)
     StopConnection::new(auto_increment.next(), stop_id_1, stop_id_2, duration)
 }
 
+/// A group can be spread over several rows, in which case the members are merged.
+fn add_stop_group(
+    mut values: Vec<ParsedValue>,
+    stop_groups: &mut FxHashMap<i32, StopGroup>,
+) -> Result<()> {
+    let id: i32 = values.remove(0).into();
+    let stop_ids: String = values.remove(0).into();
+
+    let stop_ids = stop_ids
+        .split_whitespace()
+        .map(|stop_id| stop_id.parse::<i32>())
+        .collect::<core::result::Result<Vec<_>, _>>()?;
+
+    stop_groups
+        .entry(id)
+        .or_insert_with(|| StopGroup::new(id, Vec::new()))
+        .add_stop_ids(stop_ids);
+    Ok(())
+}
+
 fn set_attribute(
     mut values: Vec<ParsedValue>,
     current_instance: &mut StopConnection,
@@ -90,3 +129,92 @@ fn set_attribute(
     current_instance.set_attribute(attribute_id);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::tests::get_json_values;
+    use pretty_assertions::assert_eq;
+
+    fn rows() -> Vec<String> {
+        vec![
+            "8500010 8500020 005".to_string(),
+            "*A Y".to_string(),
+            "8500100: 8500010 8500020".to_string(),
+            "8500100: 8500030".to_string(),
+            "8500200: 8500020".to_string(),
+        ]
+    }
+
+    #[test]
+    fn row_parser_v207() {
+        let parser = FileParser {
+            row_parser: stop_connection_row_parser().unwrap(),
+            rows: rows(),
+        };
+        let mut parser_iterator = parser.parse();
+        {
+            let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+            assert_eq!(ROW_A, id);
+            let stop_id_1: i32 = parsed_values.remove(0).into();
+            assert_eq!(8500010, stop_id_1);
+            let stop_id_2: i32 = parsed_values.remove(0).into();
+            assert_eq!(8500020, stop_id_2);
+            let duration: i16 = parsed_values.remove(0).into();
+            assert_eq!(5, duration);
+        }
+        {
+            let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+            assert_eq!(ROW_B, id);
+            let designation: String = parsed_values.remove(0).into();
+            assert_eq!("Y", &designation);
+        }
+        {
+            let (id, _, mut parsed_values) = parser_iterator.next().unwrap().unwrap();
+            assert_eq!(ROW_C, id);
+            let stop_group_id: i32 = parsed_values.remove(0).into();
+            assert_eq!(8500100, stop_group_id);
+            let stop_ids: String = parsed_values.remove(0).into();
+            assert_eq!("8500010 8500020", &stop_ids);
+        }
+    }
+
+    #[test]
+    fn type_converter_v207() {
+        let parser = FileParser {
+            row_parser: stop_connection_row_parser().unwrap(),
+            rows: rows(),
+        };
+        let attributes_pk_type_converter = FxHashMap::from_iter([("Y".to_string(), 7)]);
+        let (data, stop_groups) =
+            stop_connection_row_converter(parser, &attributes_pk_type_converter).unwrap();
+
+        assert_eq!(data.len(), 1);
+        let stop_connection = data.get(&1).unwrap();
+        let reference = r#"
+            {
+                "id": 1,
+                "stop_id_1": 8500010,
+                "stop_id_2": 8500020,
+                "duration": 5,
+                "attribute": 7
+            }"#;
+        let (stop_connection, reference) = get_json_values(stop_connection, reference).unwrap();
+        assert_eq!(stop_connection, reference);
+
+        // The two rows of the group 8500100 are merged.
+        assert_eq!(stop_groups.len(), 2);
+        let stop_group = stop_groups.get(&8500100).unwrap();
+        let reference = r#"
+            {
+                "id": 8500100,
+                "stop_ids": [8500010, 8500020, 8500030]
+            }"#;
+        let (stop_group, reference) = get_json_values(stop_group, reference).unwrap();
+        assert_eq!(stop_group, reference);
+        assert_eq!(
+            stop_groups.get(&8500200).unwrap().stop_ids(),
+            &vec![8500020]
+        );
+    }
+}
@@ -11,7 +11,7 @@ use rustc_hash::FxHashMap;
 use crate::{
     Result,
     error::{Error, ErrorKind},
-    models::{CoordinateSystem, Coordinates, Model, Stop, Version},
+    models::{CoordinateSystem, Coordinates, Model, Stop, StopRestrictions, Version},
     parsing::{
         ColumnDefinition, ExpectedType, FastRowMatcher, FileParser, ParsedValue, RowDefinition,
         RowParser,
@@ -243,8 +243,7 @@ fn set_coordinates(
     let stop_id: i32 = values.remove(0).into();
     let mut xy1: f64 = values.remove(0).into();
     let mut xy2: f64 = values.remove(0).into();
-    // Altitude is not stored, as it is not provided for 95% of stops.
-    let _altitude: i16 = values.remove(0).into();
+    let altitude: i16 = values.remove(0).into();
 
     if coordinate_system == CoordinateSystem::WGS84 {
         // WGS84 coordinates are stored in reverse order for some unknown reason.
@@ -259,6 +258,11 @@ fn set_coordinates(
         CoordinateSystem::WGS84 => stop.set_wgs84_coordinates(coordinate),
     }
 
+    // The altitude is 0 for about 95% of stops, meaning that it is not provided.
+    if altitude != 0 {
+        stop.set_altitude(Some(altitude));
+    }
+
     Ok(())
 }
 
@@ -311,7 +315,7 @@ fn set_restrictions(mut values: Vec<ParsedValue>, data: &mut FxHashMap<i32, Stop
     let restrictions: i16 = values.remove(0).into();
 
     if let Some(stop) = data.get_mut(&stop_id) {
-        stop.set_restrictions(restrictions);
+        stop.set_restrictions(StopRestrictions::from_bits(restrictions));
     } else {
         log::info!("Unknown ID: {stop_id} for restrictions");
     }
@@ -383,3 +387,25 @@ fn parse_designations(designations: String) -> Result<NameAndAlternatives> {
 
     Ok((name, long_name, abbreviation, synonyms))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn restrictions_are_decoded_from_b_rows() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hrdf");
+        let (stops, _) = parse(Version::V_5_40_41_2_0_7, path).unwrap();
+
+        // "8500004 B 01"
+        let restrictions = stops.find(8500004).unwrap().restrictions();
+        assert_eq!(StopRestrictions::NOT_SEARCHABLE, restrictions);
+        // "8500003 B 04"
+        let restrictions = stops.find(8500003).unwrap().restrictions();
+        assert_eq!(StopRestrictions::NOT_VIA, restrictions);
+        assert!(!restrictions.contains(StopRestrictions::NOT_ORIGIN_OR_DESTINATION));
+        // No B row.
+        assert!(stops.find(8500001).unwrap().restrictions().is_empty());
+    }
+}
@@ -46,7 +46,7 @@ pub fn parse(path: &str) -> Result<ResourceStorage<TimetableMetadataEntry>> {
     let end_date = NaiveDate::parse_from_str(&end_date, "%d.%m.%Y")?;
     let other_data: Vec<String> = other_data.split('$').map(String::from).collect();
 
-    let rows = vec![
+    let rows = [
         ("start_date", start_date.to_string()),
         ("end_date", end_date.to_string()),
         ("name", other_data[0].to_owned()),
@@ -0,0 +1,993 @@
+// Round-based public transport router (RAPTOR-like).
+//
+// Round k contains the stops that can be reached with k journeys (i.e. k - 1 transfers).
+// A through service (DURCHBI) is followed within the same round, since the passengers stay in the vehicle.
+// Footpaths (METABHF) are applied at the end of each round.
+
+use chrono::{Days, Duration, NaiveDate, NaiveDateTime};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    models::{Journey, Model, StopRestrictions},
+    storage::DataStorage,
+    utils::add_1_day,
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- RoutingOptions
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct RoutingOptions {
+    max_transfers: usize,
+    max_duration: Duration,
+}
+
+impl Default for RoutingOptions {
+    fn default() -> Self {
+        Self {
+            max_transfers: 8,
+            max_duration: Duration::hours(24),
+        }
+    }
+}
+
+impl RoutingOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Getters/Setters
+
+    pub fn max_transfers(&self) -> usize {
+        self.max_transfers
+    }
+
+    pub fn set_max_transfers(&mut self, value: usize) {
+        self.max_transfers = value;
+    }
+
+    /// Routes arriving later than the departure date-time + max_duration are not returned.
+    pub fn max_duration(&self) -> Duration {
+        self.max_duration
+    }
+
+    pub fn set_max_duration(&mut self, value: Duration) {
+        self.max_duration = value;
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Route
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct Route<'a> {
+    departure_at: NaiveDateTime,
+    arrival_at: NaiveDateTime,
+    transfers: usize,
+    sections: Vec<RouteSection<'a>>,
+}
+
+impl<'a> Route<'a> {
+    // Getters/Setters
+
+    pub fn departure_at(&self) -> NaiveDateTime {
+        self.departure_at
+    }
+
+    pub fn arrival_at(&self) -> NaiveDateTime {
+        self.arrival_at
+    }
+
+    /// Continuing in the same vehicle through a through service is not counted as a transfer.
+    pub fn transfers(&self) -> usize {
+        self.transfers
+    }
+
+    pub fn sections(&self) -> &Vec<RouteSection<'a>> {
+        &self.sections
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- RouteSection
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct RouteSection<'a> {
+    journey: Option<&'a Journey>,
+    service_date: Option<NaiveDate>,
+    departure_stop_id: i32,
+    arrival_stop_id: i32,
+    departure_at: NaiveDateTime,
+    arrival_at: NaiveDateTime,
+    is_through_service: bool,
+}
+
+impl<'a> RouteSection<'a> {
+    // Getters/Setters
+
+    /// A None value means that the section is a footpath.
+    pub fn journey(&self) -> Option<&'a Journey> {
+        self.journey
+    }
+
+    /// The date on which the journey leaves its first stop.
+    pub fn service_date(&self) -> Option<NaiveDate> {
+        self.service_date
+    }
+
+    pub fn departure_stop_id(&self) -> i32 {
+        self.departure_stop_id
+    }
+
+    pub fn arrival_stop_id(&self) -> i32 {
+        self.arrival_stop_id
+    }
+
+    pub fn departure_at(&self) -> NaiveDateTime {
+        self.departure_at
+    }
+
+    pub fn arrival_at(&self) -> NaiveDateTime {
+        self.arrival_at
+    }
+
+    /// Returns true if the section continues the previous one in the same vehicle (DURCHBI).
+    pub fn is_through_service(&self) -> bool {
+        self.is_through_service
+    }
+
+    // Functions
+
+    pub fn is_walking(&self) -> bool {
+        self.journey.is_none()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the Pareto-optimal routes regarding the arrival time and the number of transfers.
+    /// The routes are sorted by number of transfers; each route arrives earlier than the previous one.
+    /// The departure and arrival stops can be stop groups (METABHF), in which case any member of the group can be used.
+    pub fn pareto_routes(
+        &self,
+        departure_stop_id: i32,
+        arrival_stop_id: i32,
+        departure_at: NaiveDateTime,
+        options: &RoutingOptions,
+    ) -> Vec<Route<'_>> {
+        let arrival_stop_ids = self
+            .resolve_endpoint_stop_ids(arrival_stop_id)
+            .into_iter()
+            .collect();
+        let mut router = Router::new(self, Some(arrival_stop_ids), departure_at, options);
+        router.run(&self.resolve_endpoint_stop_ids(departure_stop_id));
+        router.routes()
+    }
+
+    /// Returns the stops that can be used as origin or destination for the stop or stop group.
+    pub(crate) fn resolve_endpoint_stop_ids(&self, stop_id: i32) -> Vec<i32> {
+        self.resolve_stop_ids(stop_id)
+            .into_iter()
+            .filter(|&stop_id| {
+                self.stops().find(stop_id).is_some_and(|stop| {
+                    !stop
+                        .restrictions()
+                        .contains(StopRestrictions::NOT_ORIGIN_OR_DESTINATION)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the route arriving the earliest, with the fewest transfers among the earliest ones.
+    pub fn earliest_arrival(
+        &self,
+        departure_stop_id: i32,
+        arrival_stop_id: i32,
+        departure_at: NaiveDateTime,
+        options: &RoutingOptions,
+    ) -> Option<Route<'_>> {
+        self.pareto_routes(departure_stop_id, arrival_stop_id, departure_at, options)
+            .pop()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Router
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+enum Label {
+    Origin {
+        departure_at: NaiveDateTime,
+    },
+    Ride {
+        arrival_at: NaiveDateTime,
+        // Index in Router.rides of the last ride leading to the stop.
+        ride: usize,
+        route_index: usize,
+    },
+    Walk {
+        arrival_at: NaiveDateTime,
+        departure_at: NaiveDateTime,
+        from_stop_id: i32,
+    },
+}
+
+impl Label {
+    fn arrival_at(&self) -> NaiveDateTime {
+        match *self {
+            Label::Origin { departure_at } => departure_at,
+            Label::Ride { arrival_at, .. } | Label::Walk { arrival_at, .. } => arrival_at,
+        }
+    }
+}
+
+struct Ride {
+    journey_id: i32,
+    service_date: NaiveDate,
+    boarding_route_index: usize,
+    // For a through service: (index of the ride that is continued, its last route index).
+    previous: Option<(usize, usize)>,
+}
+
+pub(crate) struct Router<'a> {
+    data_storage: &'a DataStorage,
+    // A None value means that all reachable stops are computed (one-to-all search).
+    arrival_stop_ids: Option<FxHashSet<i32>>,
+    // The earliest arrival at any of the arrival stops.
+    best_target_arrival: Option<NaiveDateTime>,
+    departure_at: NaiveDateTime,
+    latest_arrival_at: NaiveDateTime,
+    max_rounds: usize,
+    rides: Vec<Ride>,
+    // The best label of each stop reached in round k.
+    labels: Vec<FxHashMap<i32, Label>>,
+    // The labels of round k that were not reached by walking. The footpaths start from them.
+    transit_labels: Vec<FxHashMap<i32, Label>>,
+    best_arrivals: FxHashMap<i32, NaiveDateTime>,
+}
+
+impl<'a> Router<'a> {
+    pub(crate) fn new(
+        data_storage: &'a DataStorage,
+        arrival_stop_ids: Option<FxHashSet<i32>>,
+        departure_at: NaiveDateTime,
+        options: &RoutingOptions,
+    ) -> Self {
+        Self {
+            data_storage,
+            arrival_stop_ids,
+            best_target_arrival: None,
+            departure_at,
+            latest_arrival_at: departure_at + options.max_duration(),
+            max_rounds: options.max_transfers() + 1,
+            rides: Vec::new(),
+            labels: Vec::new(),
+            transit_labels: Vec::new(),
+            best_arrivals: FxHashMap::default(),
+        }
+    }
+
+    pub(crate) fn run(&mut self, departure_stop_ids: &[i32]) {
+        self.labels.push(FxHashMap::default());
+        self.transit_labels.push(FxHashMap::default());
+
+        let mut marked_stops = FxHashSet::default();
+        for &departure_stop_id in departure_stop_ids {
+            self.update(
+                0,
+                departure_stop_id,
+                Label::Origin {
+                    departure_at: self.departure_at,
+                },
+                &mut marked_stops,
+            );
+        }
+        self.walk(0, &mut marked_stops);
+
+        for round in 1..=self.max_rounds {
+            if marked_stops.is_empty() {
+                break;
+            }
+
+            self.labels.push(FxHashMap::default());
+            self.transit_labels.push(FxHashMap::default());
+
+            // Sorted to make the result deterministic when several routes are equivalent.
+            let mut stop_ids: Vec<i32> = marked_stops.drain().collect();
+            stop_ids.sort();
+
+            let mut scanned = FxHashMap::default();
+
+            for stop_id in stop_ids {
+                self.board_at(round, stop_id, &mut scanned, &mut marked_stops);
+            }
+
+            self.walk(round, &mut marked_stops);
+        }
+    }
+
+    /// Arrivals later than or equal to this bound cannot improve the result.
+    fn bound(&self) -> NaiveDateTime {
+        match self.best_target_arrival {
+            Some(arrival_at) => arrival_at.min(self.latest_arrival_at + Duration::minutes(1)),
+            None => self.latest_arrival_at + Duration::minutes(1),
+        }
+    }
+
+    fn update(
+        &mut self,
+        round: usize,
+        stop_id: i32,
+        label: Label,
+        marked_stops: &mut FxHashSet<i32>,
+    ) -> bool {
+        let arrival_at = label.arrival_at();
+        let is_better = self
+            .best_arrivals
+            .get(&stop_id)
+            .is_none_or(|&best| arrival_at < best);
+
+        if !is_better || arrival_at >= self.bound() {
+            return false;
+        }
+
+        self.best_arrivals.insert(stop_id, arrival_at);
+        if self
+            .arrival_stop_ids
+            .as_ref()
+            .is_some_and(|stop_ids| stop_ids.contains(&stop_id))
+        {
+            self.best_target_arrival = Some(arrival_at);
+        }
+        self.labels[round].insert(stop_id, label);
+        if !matches!(label, Label::Walk { .. }) {
+            self.transit_labels[round].insert(stop_id, label);
+        }
+        marked_stops.insert(stop_id);
+        true
+    }
+
+    fn board_at(
+        &mut self,
+        round: usize,
+        stop_id: i32,
+        scanned: &mut FxHashMap<(i32, NaiveDate), usize>,
+        marked_stops: &mut FxHashSet<i32>,
+    ) {
+        let data_storage = self.data_storage;
+        let label = self.labels[round - 1][&stop_id];
+
+        let arriving = match label {
+            Label::Ride {
+                ride, route_index, ..
+            } => {
+                if !self.can_exchange_at(stop_id) {
+                    return;
+                }
+
+                let ride = &self.rides[ride];
+                Some((ride.journey_id, ride.service_date, route_index))
+            }
+            Label::Walk { .. } => {
+                if !self.can_be_used_as_via(stop_id) {
+                    return;
+                }
+                None
+            }
+            Label::Origin { .. } => None,
+        };
+
+        for (journey, service_date, route_index, departure_at) in
+            self.departures(stop_id, label.arrival_at())
+        {
+            if departure_at >= self.bound() {
+                break;
+            }
+
+            // A negative departure time in FPLAN means that the passengers cannot board there.
+            if !journey.route()[route_index].boarding_allowed() {
+                continue;
+            }
+
+            if let Some((arriving_journey_id, arriving_service_date, arrival_route_index)) =
+                arriving
+            {
+                if arriving_journey_id == journey.id() && arriving_service_date == service_date {
+                    continue;
+                }
+
+                // unwrap: The journey of a ride always exists.
+                let arriving_journey = data_storage.journeys().find(arriving_journey_id).unwrap();
+                // unwrap: Both route entries are at the stop.
+                let exchange_time = data_storage
+                    .exchange_time(
+                        (arriving_journey, arrival_route_index),
+                        (journey, route_index),
+                        arriving_service_date,
+                    )
+                    .unwrap();
+                // The departing journey waits for the arriving one if the exchange is guaranteed.
+                let minimum_duration = if exchange_time.is_guaranteed() {
+                    0
+                } else {
+                    exchange_time.duration()
+                };
+                if departure_at < label.arrival_at() + Duration::minutes(minimum_duration.into()) {
+                    continue;
+                }
+            } else {
+                // The exchange time already includes the check-in time (*CI) when changing journeys.
+                let check_in = journey.check_in_minutes_at(route_index);
+                if departure_at < label.arrival_at() + Duration::minutes(check_in.into()) {
+                    continue;
+                }
+            }
+
+            if !mark_scanned(scanned, journey.id(), service_date, route_index) {
+                continue;
+            }
+
+            self.rides.push(Ride {
+                journey_id: journey.id(),
+                service_date,
+                boarding_route_index: route_index,
+                previous: None,
+            });
+            self.ride(round, self.rides.len() - 1, scanned, marked_stops);
+        }
+    }
+
+    /// Updates the stops served by the ride, then follows the through services of the journey.
+    fn ride(
+        &mut self,
+        round: usize,
+        ride: usize,
+        scanned: &mut FxHashMap<(i32, NaiveDate), usize>,
+        marked_stops: &mut FxHashSet<i32>,
+    ) {
+        let data_storage = self.data_storage;
+        let Ride {
+            journey_id,
+            service_date,
+            boarding_route_index,
+            ..
+        } = self.rides[ride];
+        // unwrap: The journey of a ride always exists.
+        let journey = data_storage.journeys().find(journey_id).unwrap();
+        let route_datetimes = journey.route_datetimes(service_date);
+        // The ride ends where the active section it was boarded on ends (see DatedTrip).
+        let is_hop_active = data_storage.active_hops(journey, service_date);
+        let end_route_index = (boarding_route_index..is_hop_active.len())
+            .find(|&i| !is_hop_active[i])
+            .unwrap_or(is_hop_active.len());
+
+        for (route_index, (arrival_at, _)) in route_datetimes
+            .iter()
+            .enumerate()
+            .take(end_route_index + 1)
+            .skip(boarding_route_index + 1)
+        {
+            let Some(arrival_at) = *arrival_at else {
+                continue;
+            };
+            // The passengers stay on board where alighting is not allowed (negative arrival time in FPLAN).
+            if !journey.route()[route_index].alighting_allowed() {
+                continue;
+            }
+
+            let label = Label::Ride {
+                arrival_at,
+                ride,
+                route_index,
+            };
+            self.update(
+                round,
+                journey.route()[route_index].stop_id(),
+                label,
+                marked_stops,
+            );
+        }
+
+        for (next_journey, next_service_date, next_route_index, last_route_index) in self
+            .data_storage
+            .through_service_continuations(journey, service_date, &route_datetimes)
+            .into_iter()
+            .filter(|&(_, _, _, last_route_index)| {
+                last_route_index > boarding_route_index && last_route_index <= end_route_index
+            })
+        {
+            if !mark_scanned(
+                scanned,
+                next_journey.id(),
+                next_service_date,
+                next_route_index,
+            ) {
+                continue;
+            }
+
+            self.rides.push(Ride {
+                journey_id: next_journey.id(),
+                service_date: next_service_date,
+                boarding_route_index: next_route_index,
+                previous: Some((ride, last_route_index)),
+            });
+            self.ride(round, self.rides.len() - 1, scanned, marked_stops);
+        }
+    }
+
+    fn walk(&mut self, round: usize, marked_stops: &mut FxHashSet<i32>) {
+        let data_storage = self.data_storage;
+        let mut sources: Vec<(i32, Label)> = self.transit_labels[round]
+            .iter()
+            .map(|(&stop_id, &label)| (stop_id, label))
+            .collect();
+        sources.sort_by_key(|(stop_id, _)| *stop_id);
+
+        for (stop_id, label) in sources {
+            if matches!(label, Label::Ride { .. }) && !self.can_exchange_at(stop_id) {
+                continue;
+            }
+
+            let Some(stop_connection_ids) =
+                data_storage.stop_connections_by_stop_id().get(&stop_id)
+            else {
+                continue;
+            };
+
+            // The passengers can only leave the stop once the check-out time (*CO) is over.
+            let departure_at = match label {
+                Label::Ride {
+                    ride, route_index, ..
+                } => {
+                    // unwrap: The journey of a ride always exists.
+                    let journey = data_storage
+                        .journeys()
+                        .find(self.rides[ride].journey_id)
+                        .unwrap();
+                    label.arrival_at()
+                        + Duration::minutes(journey.check_out_minutes_at(route_index).into())
+                }
+                _ => label.arrival_at(),
+            };
+
+            for &stop_connection_id in stop_connection_ids {
+                let Some(stop_connection) =
+                    data_storage.stop_connections().find(stop_connection_id)
+                else {
+                    continue;
+                };
+
+                let label = Label::Walk {
+                    arrival_at: departure_at + Duration::minutes(stop_connection.duration().into()),
+                    departure_at,
+                    from_stop_id: stop_id,
+                };
+                self.update(round, stop_connection.stop_id_2(), label, marked_stops);
+            }
+        }
+    }
+
+    fn can_exchange_at(&self, stop_id: i32) -> bool {
+        self.can_be_used_as_via(stop_id)
+            && self
+                .data_storage
+                .stops()
+                .find(stop_id)
+                .is_some_and(|stop| stop.can_be_used_as_exchange_point())
+    }
+
+    fn can_be_used_as_via(&self, stop_id: i32) -> bool {
+        self.data_storage
+            .stops()
+            .find(stop_id)
+            .is_some_and(|stop| !stop.restrictions().contains(StopRestrictions::NOT_VIA))
+    }
+
+    /// Returns the (journey, service date, route index, departure date-time) of the departures from the stop
+    /// between from (inclusive) and the current bound (exclusive), sorted by departure date-time.
+    /// Only the departures starting an active section are returned (see DatedTrip).
+    fn departures(
+        &self,
+        stop_id: i32,
+        from: NaiveDateTime,
+    ) -> Vec<(&'a Journey, NaiveDate, usize, NaiveDateTime)> {
+        let data_storage = self.data_storage;
+        let until = self.bound();
+        let Some(bit_field_ids) = data_storage.bit_fields_by_stop_id().get(&stop_id) else {
+            return Vec::new();
+        };
+
+        let mut result = Vec::new();
+        // Journeys that started on a previous day can still serve the stop, possibly several days later.
+        let mut service_date = from.date() - Days::new(data_storage.max_trip_days() as u64);
+
+        while service_date <= until.date() {
+            for journey in data_storage.journeys_at_stop_on(stop_id, bit_field_ids, service_date) {
+                let route_datetimes = journey.route_datetimes(service_date);
+                let is_hop_active = data_storage.active_hops(journey, service_date);
+
+                for (route_index, route_entry) in journey.route().iter().enumerate() {
+                    if route_entry.stop_id() != stop_id
+                        || !is_hop_active.get(route_index).copied().unwrap_or(false)
+                    {
+                        continue;
+                    }
+
+                    if let Some(departure_at) = route_datetimes[route_index]
+                        .1
+                        .filter(|departure_at| from <= *departure_at && *departure_at < until)
+                    {
+                        result.push((journey, service_date, route_index, departure_at));
+                    }
+                }
+            }
+
+            service_date = add_1_day(service_date);
+        }
+
+        result.sort_by_key(|&(journey, _, route_index, departure_at)| {
+            (departure_at, journey.id(), route_index)
+        });
+        result
+    }
+
+    /// Returns the (stop ID, earliest arrival date-time, number of transfers) of all the stops reached.
+    pub(crate) fn reached_stops(&self) -> Vec<(i32, NaiveDateTime, usize)> {
+        self.best_arrivals
+            .iter()
+            .map(|(&stop_id, &arrival_at)| {
+                // The earliest arrival is always found in the last round in which the stop was reached.
+                let round = (0..self.labels.len())
+                    .rfind(|&round| self.labels[round].contains_key(&stop_id))
+                    .unwrap_or(0);
+                (stop_id, arrival_at, round.saturating_sub(1))
+            })
+            .collect()
+    }
+
+    fn routes(&self) -> Vec<Route<'a>> {
+        let Some(arrival_stop_ids) = &self.arrival_stop_ids else {
+            return Vec::new();
+        };
+
+        let mut routes: Vec<Route<'a>> = Vec::new();
+
+        for round in 0..self.labels.len() {
+            // The arrival stop reached the earliest in this round.
+            let Some((arrival_at, arrival_stop_id)) = arrival_stop_ids
+                .iter()
+                .filter_map(|stop_id| {
+                    let label = self.labels[round].get(stop_id)?;
+                    Some((label.arrival_at(), *stop_id))
+                })
+                .min()
+            else {
+                continue;
+            };
+
+            // With several arrival stops, a later round can improve one of them without improving the result.
+            if routes
+                .last()
+                .is_none_or(|route| arrival_at < route.arrival_at)
+            {
+                routes.push(self.route(round, arrival_stop_id));
+            }
+        }
+
+        routes
+    }
+
+    fn route(&self, round: usize, arrival_stop_id: i32) -> Route<'a> {
+        let data_storage = self.data_storage;
+        let mut sections = Vec::new();
+        let mut round = round;
+        let mut stop_id = arrival_stop_id;
+        let mut label = self.labels[round][&stop_id];
+        let arrival_at = label.arrival_at();
+        let transfers = round.saturating_sub(1);
+
+        loop {
+            match label {
+                Label::Origin { .. } => break,
+                Label::Walk {
+                    arrival_at,
+                    departure_at,
+                    from_stop_id,
+                } => {
+                    sections.push(RouteSection {
+                        journey: None,
+                        service_date: None,
+                        departure_stop_id: from_stop_id,
+                        arrival_stop_id: stop_id,
+                        departure_at,
+                        arrival_at,
+                        is_through_service: false,
+                    });
+                    stop_id = from_stop_id;
+                    label = self.transit_labels[round][&stop_id];
+                }
+                Label::Ride {
+                    ride, route_index, ..
+                } => {
+                    let mut ride = ride;
+                    let mut last_route_index = route_index;
+
+                    loop {
+                        let current = &self.rides[ride];
+                        // unwrap: The journey of a ride always exists.
+                        let journey = data_storage.journeys().find(current.journey_id).unwrap();
+                        let route_datetimes = journey.route_datetimes(current.service_date);
+                        let boarding_route_index = current.boarding_route_index;
+
+                        sections.push(RouteSection {
+                            journey: Some(journey),
+                            service_date: Some(current.service_date),
+                            departure_stop_id: journey.route()[boarding_route_index].stop_id(),
+                            arrival_stop_id: journey.route()[last_route_index].stop_id(),
+                            // unwrap: A ride is only boarded where there is a departure time.
+                            departure_at: route_datetimes[boarding_route_index].1.unwrap(),
+                            // unwrap: A ride only updates stops where there is an arrival time.
+                            arrival_at: route_datetimes[last_route_index].0.unwrap(),
+                            is_through_service: current.previous.is_some(),
+                        });
+
+                        match current.previous {
+                            Some((previous_ride, previous_last_route_index)) => {
+                                ride = previous_ride;
+                                last_route_index = previous_last_route_index;
+                            }
+                            None => {
+                                stop_id = journey.route()[boarding_route_index].stop_id();
+                                break;
+                            }
+                        }
+                    }
+
+                    round -= 1;
+                    label = self.labels[round][&stop_id];
+                }
+            }
+        }
+
+        sections.reverse();
+
+        Route {
+            departure_at: sections
+                .first()
+                .map_or(self.departure_at, |section| section.departure_at),
+            arrival_at,
+            transfers,
+            sections,
+        }
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Returns false if the journey has already been scanned in this round from the same or an earlier route index.
+fn mark_scanned(
+    scanned: &mut FxHashMap<(i32, NaiveDate), usize>,
+    journey_id: i32,
+    service_date: NaiveDate,
+    route_index: usize,
+) -> bool {
+    match scanned.get(&(journey_id, service_date)) {
+        Some(&i) if i <= route_index => false,
+        _ => {
+            scanned.insert((journey_id, service_date), route_index);
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn summarize(route: &Route) -> (NaiveDateTime, usize, Vec<Option<i32>>) {
+        (
+            route.arrival_at(),
+            route.transfers(),
+            route
+                .sections()
+                .iter()
+                .map(|section| section.journey().map(|journey| journey.id()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn direct_route() {
+        let data_storage = fixture_data_storage();
+        let route = data_storage
+            .earliest_arrival(
+                8500001,
+                8500003,
+                datetime("2025-03-03 07:50"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            (datetime("2025-03-03 09:00"), 0, vec![Some(1)]),
+            summarize(&route)
+        );
+        assert_eq!(datetime("2025-03-03 08:00"), route.departure_at());
+    }
+
+    #[test]
+    fn route_with_one_transfer() {
+        let data_storage = fixture_data_storage();
+        let routes = data_storage.pareto_routes(
+            8500001,
+            8500004,
+            datetime("2025-03-03 07:50"),
+            &RoutingOptions::new(),
+        );
+
+        // The direct IR arrives at 10:30, changing at Beta from the IC to the bus is faster.
+        assert_eq!(
+            vec![
+                (datetime("2025-03-03 10:30"), 0, vec![Some(4)]),
+                (datetime("2025-03-03 09:20"), 1, vec![Some(1), Some(11)]),
+            ],
+            routes.iter().map(summarize).collect::<Vec<_>>()
+        );
+        assert_eq!(8500002, routes[1].sections()[0].arrival_stop_id());
+    }
+
+    #[test]
+    fn stop_groups_as_endpoints() {
+        let data_storage = fixture_data_storage();
+        let route = data_storage
+            .earliest_arrival(
+                8500008,
+                8500009,
+                datetime("2025-03-03 06:50"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+
+        // Alpha and Epsilon are members of the groups 8500008 and 8500009, the through service 6-7 links them.
+        assert_eq!(
+            (datetime("2025-03-03 07:50"), 0, vec![Some(6), Some(7)]),
+            summarize(&route)
+        );
+        assert_eq!(8500001, route.sections()[0].departure_stop_id());
+        assert_eq!(8500005, route.sections()[1].arrival_stop_id());
+
+        // Later, Gamma is the member of the group 8500009 reached first.
+        let route = data_storage
+            .earliest_arrival(
+                8500008,
+                8500009,
+                datetime("2025-03-03 07:50"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+        assert_eq!(
+            (datetime("2025-03-03 09:00"), 0, vec![Some(1)]),
+            summarize(&route)
+        );
+        assert_eq!(8500003, route.sections()[0].arrival_stop_id());
+    }
+
+    #[test]
+    fn unreachable_target() {
+        let data_storage = fixture_data_storage();
+
+        // No journey leaves Delta.
+        assert!(
+            data_storage
+                .earliest_arrival(
+                    8500004,
+                    8500001,
+                    datetime("2025-03-03 07:50"),
+                    &RoutingOptions::new(),
+                )
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn no_boarding_where_the_departure_time_is_negative() {
+        let data_storage = fixture_data_storage();
+        let route = data_storage
+            .earliest_arrival(
+                8500002,
+                8500003,
+                datetime("2025-03-03 12:00"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+
+        // The journeys 8 and 13 leave Beta at 12:11 and 12:41, but the passengers cannot board there.
+        assert_eq!(
+            (datetime("2025-03-03 13:30"), 0, vec![Some(10)]),
+            summarize(&route)
+        );
+    }
+
+    #[test]
+    fn no_alighting_where_the_arrival_time_is_negative() {
+        let data_storage = fixture_data_storage();
+        let route = data_storage
+            .earliest_arrival(
+                8500001,
+                8500002,
+                datetime("2025-03-03 11:55"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+
+        // The journey 8 reaches Beta at 12:10, but the passengers cannot alight there.
+        assert_eq!(
+            (datetime("2025-03-03 13:10"), 0, vec![Some(10)]),
+            summarize(&route)
+        );
+    }
+
+    #[test]
+    fn later_trip_of_the_same_stops_allowing_alighting() {
+        let data_storage = fixture_data_storage();
+        let route = data_storage
+            .earliest_arrival(
+                8500001,
+                8500002,
+                datetime("2025-03-03 12:20"),
+                &RoutingOptions::new(),
+            )
+            .unwrap();
+
+        // The journeys 13 and 10 serve the same stops, only the later one lets the passengers alight at Beta.
+        assert_eq!(
+            (datetime("2025-03-03 13:10"), 0, vec![Some(10)]),
+            summarize(&route)
+        );
+    }
+
+    #[test]
+    fn inactive_sections_are_not_ridden() {
+        let data_storage = fixture_data_storage();
+        let earliest_arrival = |departure_stop_id, departure_at| {
+            data_storage
+                .earliest_arrival(
+                    departure_stop_id,
+                    8500004,
+                    datetime(departure_at),
+                    &RoutingOptions::new(),
+                )
+                .map(|route| summarize(&route))
+        };
+
+        // The section of the journey 8 from Gamma to Delta only runs on the first two days of the timetable,
+        // Delta is only reached on the next morning.
+        assert_eq!(
+            Some((datetime("2025-03-04 09:20"), 1, vec![Some(10), Some(11)])),
+            earliest_arrival(8500001, "2025-03-03 11:55")
+        );
+        assert_eq!(
+            Some((datetime("2025-03-04 09:30"), 0, vec![Some(2)])),
+            earliest_arrival(8500003, "2025-03-03 11:55")
+        );
+        assert_eq!(
+            Some((datetime("2025-01-01 12:30"), 0, vec![Some(8)])),
+            earliest_arrival(8500003, "2025-01-01 11:55")
+        );
+    }
+}
@@ -0,0 +1,438 @@
+// The names, long names, abbreviations and synonyms of the stops are normalized (lowercase, no accents,
+// no punctuation) and split into trigrams. Each word is padded with two leading spaces and one trailing
+// space, so that a query shares at least its leading trigrams with the words it is a prefix of. Its
+// trailing trigram is usually not shared, which is why prefixes are matched separately from the similarity.
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{Model, Stop, StopRestrictions},
+    storage::{DataStorage, ResourceStorage},
+};
+
+// Minimum trigram similarity (Jaccard index) for a fuzzy match.
+const MIN_SIMILARITY: f64 = 0.3;
+// Lowest exchange priority in BFPRIOS, 0 being the highest.
+const LOWEST_EXCHANGE_PRIORITY: i16 = 16;
+
+// ------------------------------------------------------------------------------------------------
+// --- StopSearchIndex
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchEntry {
+    stop_id: i32,
+    text: String,
+    normalized_text: String,
+    trigram_count: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StopSearchIndex {
+    entries: Vec<SearchEntry>,
+    entries_by_trigram: FxHashMap<String, Vec<usize>>,
+}
+
+impl StopSearchIndex {
+    pub(crate) fn new(stops: &ResourceStorage<Stop>) -> Self {
+        let mut stops = stops.entries();
+        // Sorted to make the index deterministic.
+        stops.sort_by_key(|stop| stop.id());
+
+        let mut index = Self::default();
+
+        for stop in stops {
+            let texts = [Some(stop.name()), stop.long_name(), stop.abbreviation()]
+                .into_iter()
+                .flatten()
+                .chain(stop.synonyms().into_iter().flatten().map(|s| s.as_str()));
+
+            let mut seen = FxHashSet::default();
+            for text in texts {
+                let normalized_text = normalize(text);
+                if normalized_text.is_empty() || !seen.insert(normalized_text.clone()) {
+                    continue;
+                }
+                index.insert(stop.id(), text, normalized_text);
+            }
+        }
+
+        index
+    }
+
+    // Getters/Setters
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Functions
+
+    fn insert(&mut self, stop_id: i32, text: &str, normalized_text: String) {
+        let trigrams = trigrams(&normalized_text);
+        let entry_index = self.entries.len();
+
+        for trigram in &trigrams {
+            self.entries_by_trigram
+                .entry(trigram.to_owned())
+                .or_default()
+                .push(entry_index);
+        }
+
+        self.entries.push(SearchEntry {
+            stop_id,
+            text: text.to_owned(),
+            normalized_text,
+            trigram_count: trigrams.len(),
+        });
+    }
+
+    /// Returns the best match of each stop as (stop_id, match score, matched text) tuples, in no particular order.
+    /// The match score is between 0.0 and 1.0:
+    /// * 1.0: the query is equal to the text,
+    /// * 0.8: the query is a prefix of the text,
+    /// * 0.6: each word of the query is a prefix of a word of the text,
+    /// * below 0.5: the trigram similarity between the query and the text, times 0.5.
+    pub fn search(&self, query: &str) -> Vec<(i32, f64, &str)> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let query_trigrams = trigrams(&query);
+        let mut shared_trigrams: FxHashMap<usize, usize> = FxHashMap::default();
+        for trigram in &query_trigrams {
+            for &entry_index in self.entries_by_trigram.get(trigram).into_iter().flatten() {
+                *shared_trigrams.entry(entry_index).or_default() += 1;
+            }
+        }
+
+        let query_words: Vec<&str> = query.split(' ').collect();
+        let mut best_matches: FxHashMap<i32, (f64, &str)> = FxHashMap::default();
+
+        for (entry_index, shared) in shared_trigrams {
+            let entry = &self.entries[entry_index];
+
+            let score = if entry.normalized_text == query {
+                1.0
+            } else if entry.normalized_text.starts_with(&query) {
+                0.8
+            } else if query_words.iter().all(|query_word| {
+                entry
+                    .normalized_text
+                    .split(' ')
+                    .any(|word| word.starts_with(query_word))
+            }) {
+                0.6
+            } else {
+                let similarity =
+                    shared as f64 / (query_trigrams.len() + entry.trigram_count - shared) as f64;
+                if similarity < MIN_SIMILARITY {
+                    continue;
+                }
+                similarity * 0.5
+            };
+
+            let best_match = best_matches
+                .entry(entry.stop_id)
+                .or_insert((score, &entry.text));
+            if score > best_match.0 {
+                *best_match = (score, &entry.text);
+            }
+        }
+
+        best_matches
+            .into_iter()
+            .map(|(stop_id, (score, text))| (stop_id, score, text))
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- StopSearchResult
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct StopSearchResult<'a> {
+    stop: &'a Stop,
+    score: f64,
+    matched_text: &'a str,
+}
+
+impl<'a> StopSearchResult<'a> {
+    // Getters/Setters
+
+    pub fn stop(&self) -> &'a Stop {
+        self.stop
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// The name, long name, abbreviation or synonym that matched the query.
+    pub fn matched_text(&self) -> &'a str {
+        self.matched_text
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns at most limit stops matching the query, sorted by descending score.
+    /// The score is the match score (see StopSearchIndex::search), plus up to 0.5 for the exchange priority, plus the boost.
+    /// The members of a matching stop group (METABHF) are also returned, with the match score of the group.
+    /// Stops with a negative score or marked as not searchable in BHFART are discarded.
+    pub fn search_stops<F>(&self, query: &str, limit: usize, boost: F) -> Vec<StopSearchResult<'_>>
+    where
+        F: Fn(&Stop) -> f64,
+    {
+        let matches = self.stop_search_index().search(query);
+
+        // The members of a matching stop group match as well, e.g. the tram stops of a station.
+        let mut best_matches: FxHashMap<i32, (f64, &str)> = matches
+            .iter()
+            .map(|&(stop_id, match_score, matched_text)| (stop_id, (match_score, matched_text)))
+            .collect();
+        for &(stop_id, match_score, matched_text) in &matches {
+            let Some(stop_group) = self.stop_groups().find(stop_id) else {
+                continue;
+            };
+            for &member_id in stop_group.stop_ids() {
+                let best_match = best_matches
+                    .entry(member_id)
+                    .or_insert((match_score, matched_text));
+                if match_score > best_match.0 {
+                    *best_match = (match_score, matched_text);
+                }
+            }
+        }
+
+        let mut results: Vec<_> = best_matches
+            .into_iter()
+            .filter_map(|(stop_id, (match_score, matched_text))| {
+                let stop = self.stops().find(stop_id).filter(|stop| {
+                    !stop
+                        .restrictions()
+                        .contains(StopRestrictions::NOT_SEARCHABLE)
+                })?;
+                let priority = stop.exchange_priority().clamp(0, LOWEST_EXCHANGE_PRIORITY);
+                let priority_score = 0.5 * f64::from(LOWEST_EXCHANGE_PRIORITY - priority)
+                    / f64::from(LOWEST_EXCHANGE_PRIORITY);
+
+                Some(StopSearchResult {
+                    stop,
+                    score: match_score + priority_score + boost(stop),
+                    matched_text,
+                })
+            })
+            .filter(|result| result.score >= 0.0)
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.stop.name().cmp(b.stop.name()))
+                .then(a.stop.id().cmp(&b.stop.id()))
+        });
+        results.truncate(limit);
+        results
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Lowercases the text, removes the accents and replaces the punctuation by spaces.
+/// For example, "Genève, Cornavin" becomes "geneve cornavin".
+fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        let replacement = match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+            'æ' => "ae",
+            'ç' => "c",
+            'è' | 'é' | 'ê' | 'ë' => "e",
+            'ì' | 'í' | 'î' | 'ï' => "i",
+            'ñ' => "n",
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+            'œ' => "oe",
+            'ß' => "ss",
+            'ù' | 'ú' | 'û' | 'ü' => "u",
+            'ý' | 'ÿ' => "y",
+            c if c.is_alphanumeric() => {
+                normalized.push(c);
+                continue;
+            }
+            _ => " ",
+        };
+        normalized.push_str(replacement);
+    }
+
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn trigrams(normalized_text: &str) -> FxHashSet<String> {
+    normalized_text
+        .split(' ')
+        .flat_map(|word| {
+            let padded: Vec<char> = format!("  {word} ").chars().collect();
+            padded
+                .windows(3)
+                .map(|window| window.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn search_index() -> StopSearchIndex {
+        let stops = [
+            Stop::new(1, "Zürich HB".to_owned(), None, None, None),
+            Stop::new(2, "Genève".to_owned(), None, None, None),
+            Stop::new(3, "Zurich".to_owned(), None, None, None),
+            Stop::new(
+                4,
+                "Flughafen".to_owned(),
+                None,
+                None,
+                Some(vec!["Zürich Flughafen".to_owned()]),
+            ),
+            Stop::new(5, "Zurigo".to_owned(), None, None, None),
+        ];
+        StopSearchIndex::new(&ResourceStorage::new(
+            stops.into_iter().map(|stop| (stop.id(), stop)).collect(),
+        ))
+    }
+
+    fn search(index: &StopSearchIndex, query: &str) -> Vec<(i32, f64, String)> {
+        let mut results: Vec<_> = index
+            .search(query)
+            .into_iter()
+            .map(|(stop_id, score, text)| (stop_id, score, text.to_owned()))
+            .collect();
+        results.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        results
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(super::normalize("Genève, Cornavin"), "geneve cornavin");
+        assert_eq!(super::normalize("  Zürich   HB "), "zurich hb");
+        assert_eq!(super::normalize("St. Gallen"), "st gallen");
+        assert_eq!(super::normalize("Œuvre Straße"), "oeuvre strasse");
+        assert_eq!(super::normalize("..."), "");
+    }
+
+    #[test]
+    fn search_folds_accents() {
+        let index = search_index();
+        assert_eq!(index.len(), 6);
+
+        assert_eq!(
+            search(&index, "Geneve"),
+            vec![(2, 1.0, "Genève".to_owned())]
+        );
+        assert_eq!(
+            search(&index, "GENÈVE"),
+            vec![(2, 1.0, "Genève".to_owned())]
+        );
+        // "Zürich" and "Zurich" are the same text once normalized.
+        assert_eq!(search(&index, "Zürich"), search(&index, "Zurich"));
+    }
+
+    #[test]
+    fn search_ranking() {
+        let index = search_index();
+
+        // Similarity of "zurigo": 4 shared trigrams out of 10, times 0.5.
+        assert_eq!(
+            search(&index, "zurich"),
+            vec![
+                (3, 1.0, "Zurich".to_owned()),
+                (1, 0.8, "Zürich HB".to_owned()),
+                (4, 0.8, "Zürich Flughafen".to_owned()),
+                (5, 0.2, "Zurigo".to_owned()),
+            ]
+        );
+        // Each word of the query is a prefix of a word of the synonym.
+        assert_eq!(
+            search(&index, "flug zur"),
+            vec![(4, 0.6, "Zürich Flughafen".to_owned())]
+        );
+        assert!(search(&index, "").is_empty());
+        assert!(search(&index, "bern").is_empty());
+    }
+
+    #[test]
+    fn search_stops_boost() {
+        let data_storage = fixture_data_storage();
+        let summarize = |results: Vec<StopSearchResult>| -> Vec<(i32, String)> {
+            results
+                .into_iter()
+                .map(|result| (result.stop().id(), format!("{:.2}", result.score())))
+                .collect()
+        };
+
+        // Both stops only match by similarity: 6 of 11 trigrams for Gamma, 5 of 11 for Beta.
+        assert_eq!(
+            summarize(data_storage.search_stops("Gamma Beta", 10, |_| 0.0)),
+            vec![(8500003, "0.27".to_owned()), (8500002, "0.23".to_owned())]
+        );
+        assert_eq!(
+            summarize(data_storage.search_stops("Gamma Beta", 10, |stop| {
+                if stop.id() == 8500002 { 0.1 } else { 0.0 }
+            })),
+            vec![(8500002, "0.33".to_owned()), (8500003, "0.27".to_owned())]
+        );
+        assert_eq!(
+            summarize(data_storage.search_stops("Gamma Beta", 1, |_| 0.0)),
+            vec![(8500003, "0.27".to_owned())]
+        );
+        // A negative score discards the stop.
+        assert_eq!(
+            summarize(data_storage.search_stops("Gamma Beta", 10, |stop| {
+                if stop.id() == 8500003 { -1.0 } else { 0.0 }
+            })),
+            vec![(8500002, "0.23".to_owned())]
+        );
+        // Delta is not searchable (BHFART), whatever its score.
+        assert_eq!(
+            summarize(data_storage.search_stops("Delta Beta", 10, |_| 1.0)),
+            vec![(8500002, "1.25".to_owned())]
+        );
+    }
+
+    #[test]
+    fn search_stops_expands_stop_groups() {
+        let data_storage = fixture_data_storage();
+        let results: Vec<_> = data_storage
+            .search_stops("Zentrum", 10, |_| 0.0)
+            .into_iter()
+            .map(|result| (result.stop().id(), result.score(), result.matched_text()))
+            .collect();
+
+        // The members of the group 8500009 get the match score of the group, but their own priority (16).
+        assert_eq!(
+            results,
+            vec![
+                (8500009, 1.25, "Zentrum"),
+                (8500005, 1.0, "Zentrum"),
+                (8500003, 1.0, "Zentrum"),
+            ]
+        );
+    }
+}
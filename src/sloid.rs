@@ -0,0 +1,301 @@
+use std::{fmt, str::FromStr};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    error::{Error, ErrorKind},
+    models::{Model, Platform, Stop},
+    storage::{DataStorage, ResourceStorage},
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- Sloid
+// ------------------------------------------------------------------------------------------------
+
+/// A Swiss Location ID, e.g. "ch:1:sloid:7000:4:7".
+/// The parts after the stop number identify an area of the stop, then a platform of the area.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Sloid {
+    country: String,
+    stop_number: i32,
+    sub_parts: Vec<String>,
+}
+
+impl Sloid {
+    // Getters/Setters
+
+    /// The country code, e.g. "ch".
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    /// The stop number without the UIC country code, e.g. 7000 for the stop 8507000.
+    pub fn stop_number(&self) -> i32 {
+        self.stop_number
+    }
+
+    pub fn sub_parts(&self) -> &Vec<String> {
+        &self.sub_parts
+    }
+
+    pub fn area(&self) -> Option<&str> {
+        self.sub_parts.first().map(|s| s.as_str())
+    }
+
+    pub fn platform(&self) -> Option<&str> {
+        self.sub_parts.get(1).map(|s| s.as_str())
+    }
+
+    // Functions
+
+    pub fn is_stop(&self) -> bool {
+        self.sub_parts.is_empty()
+    }
+
+    /// Returns the SLOID of the stop, i.e. without sub-parts.
+    pub fn stop_sloid(&self) -> Sloid {
+        self.truncated(0)
+    }
+
+    /// Returns the SLOID of the area, or None if the SLOID is the one of a stop.
+    pub fn area_sloid(&self) -> Option<Sloid> {
+        (!self.sub_parts.is_empty()).then(|| self.truncated(1))
+    }
+
+    /// Returns the SLOID one level up in the hierarchy (platform → area → stop).
+    pub fn parent(&self) -> Option<Sloid> {
+        (!self.sub_parts.is_empty()).then(|| self.truncated(self.sub_parts.len() - 1))
+    }
+
+    /// Returns true if other is this SLOID or one of its descendants.
+    pub fn contains(&self, other: &Sloid) -> bool {
+        self.country == other.country
+            && self.stop_number == other.stop_number
+            && other.sub_parts.starts_with(&self.sub_parts)
+    }
+
+    fn truncated(&self, len: usize) -> Sloid {
+        Sloid {
+            country: self.country.clone(),
+            stop_number: self.stop_number,
+            sub_parts: self.sub_parts[..len.min(self.sub_parts.len())].to_vec(),
+        }
+    }
+}
+
+impl FromStr for Sloid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+
+        // <country>:<version>:sloid:<stop number>[:<area>[:<platform>]]
+        match parts.as_slice() {
+            [country, "1", "sloid", stop_number, sub_parts @ ..]
+                if !country.is_empty()
+                    && sub_parts.len() <= 2
+                    && sub_parts.iter().all(|part| !part.is_empty()) =>
+            {
+                if let Ok(stop_number) = stop_number.parse() {
+                    return Ok(Sloid {
+                        country: country.to_string(),
+                        stop_number,
+                        sub_parts: sub_parts.iter().map(|part| part.to_string()).collect(),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        Err(ErrorKind::InvalidSloid {
+            value: s.to_owned(),
+        }
+        .into())
+    }
+}
+
+impl fmt::Display for Sloid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:1:sloid:{}", self.country, self.stop_number)?;
+        for part in &self.sub_parts {
+            write!(f, ":{part}")?;
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the stop with this SLOID. The SLOID of an area or a platform resolves to its stop.
+    pub fn stop_by_sloid(&self, sloid: &str) -> Option<&Stop> {
+        let sloid = Sloid::from_str(sloid).ok()?.stop_sloid();
+        self.stops_by_sloid()
+            .get(&sloid.to_string())
+            .and_then(|&id| self.stops().find(id))
+    }
+
+    pub fn platform_by_sloid(&self, sloid: &str) -> Option<&Platform> {
+        let sloid = Sloid::from_str(sloid).ok()?;
+        self.platforms_by_sloid()
+            .get(&sloid.to_string())
+            .and_then(|&id| self.platforms().find(id))
+    }
+
+    /// Returns the platforms located in the stop or the area with this SLOID, sorted by SLOID.
+    pub fn platforms_in_sloid(&self, sloid: &str) -> Vec<&Platform> {
+        let Ok(parent) = Sloid::from_str(sloid) else {
+            return Vec::new();
+        };
+        let Some(ids) = self
+            .platforms_by_stop_sloid()
+            .get(&parent.stop_sloid().to_string())
+        else {
+            return Vec::new();
+        };
+
+        let mut platforms: Vec<(Sloid, &Platform)> = ids
+            .iter()
+            .filter_map(|&id| self.platforms().find(id))
+            .filter_map(|platform| Some((Sloid::from_str(platform.sloid()).ok()?, platform)))
+            .filter(|(sloid, _)| parent.contains(sloid))
+            .collect();
+        platforms.sort_by(|a, b| {
+            a.0.sub_parts
+                .cmp(&b.0.sub_parts)
+                .then(a.1.id().cmp(&b.1.id()))
+        });
+        platforms
+            .into_iter()
+            .map(|(_, platform)| platform)
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+/// The SLOIDs are normalized. Stops without a valid SLOID are not indexed.
+pub(crate) fn create_stops_by_sloid(stops: &ResourceStorage<Stop>) -> FxHashMap<String, i32> {
+    stops
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, stop| {
+            if let Ok(sloid) = Sloid::from_str(stop.sloid()) {
+                acc.insert(sloid.to_string(), stop.id());
+            }
+            acc
+        })
+}
+
+/// The SLOIDs are normalized. Platforms without a valid SLOID are not indexed.
+pub(crate) fn create_platforms_by_sloid(
+    platforms: &ResourceStorage<Platform>,
+) -> FxHashMap<String, i32> {
+    platforms
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, platform| {
+            if let Ok(sloid) = Sloid::from_str(platform.sloid()) {
+                acc.insert(sloid.to_string(), platform.id());
+            }
+            acc
+        })
+}
+
+/// The platforms are indexed by the SLOID of their stop.
+pub(crate) fn create_platforms_by_stop_sloid(
+    platforms: &ResourceStorage<Platform>,
+) -> FxHashMap<String, Vec<i32>> {
+    let mut platforms_by_stop_sloid = platforms.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<String, Vec<i32>>, platform| {
+            if let Ok(sloid) = Sloid::from_str(platform.sloid()) {
+                acc.entry(sloid.stop_sloid().to_string())
+                    .or_default()
+                    .push(platform.id());
+            }
+            acc
+        },
+    );
+    platforms_by_stop_sloid
+        .values_mut()
+        .for_each(|ids| ids.sort());
+    platforms_by_stop_sloid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_str_stop() {
+        let sloid = Sloid::from_str("ch:1:sloid:7000").unwrap();
+        assert_eq!("ch", sloid.country());
+        assert_eq!(7000, sloid.stop_number());
+        assert!(sloid.is_stop());
+        assert_eq!(None, sloid.area());
+        assert_eq!("ch:1:sloid:7000", sloid.to_string());
+    }
+
+    #[test]
+    fn from_str_platform() {
+        let sloid = Sloid::from_str(" ch:1:sloid:7000:4:7 ").unwrap();
+        assert_eq!(7000, sloid.stop_number());
+        assert_eq!(Some("4"), sloid.area());
+        assert_eq!(Some("7"), sloid.platform());
+        assert_eq!("ch:1:sloid:7000:4", sloid.parent().unwrap().to_string());
+        assert_eq!("ch:1:sloid:7000", sloid.stop_sloid().to_string());
+        assert!(sloid.stop_sloid().contains(&sloid));
+        assert!(!sloid.contains(&sloid.stop_sloid()));
+    }
+
+    #[test]
+    fn from_str_invalid() {
+        for s in [
+            "ch:2:sloid:7000",
+            "ch:1:sloid:7000:4:7:1",
+            "ch:1:sloid:abc",
+            "ch:1:sloid:7000::7",
+            "ch:1:sloid",
+            ":1:sloid:7000",
+            "",
+        ] {
+            assert!(Sloid::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        let data_storage = fixture_data_storage();
+
+        let stop_id = |sloid| data_storage.stop_by_sloid(sloid).map(|stop| stop.id());
+        assert_eq!(Some(8500001), stop_id("ch:1:sloid:1"));
+        assert_eq!(Some(8500001), stop_id("ch:1:sloid:1:1:7"));
+        assert_eq!(Some(8500002), stop_id("ch:1:sloid:2"));
+        assert_eq!(None, stop_id("ch:1:sloid:3"));
+        assert_eq!(None, stop_id("invalid"));
+
+        let platform = data_storage.platform_by_sloid("ch:1:sloid:1:1:7").unwrap();
+        assert_eq!(8500001, platform.stop_id());
+        assert_eq!("7", platform.name());
+        assert!(data_storage.platform_by_sloid("ch:1:sloid:1:1:8").is_none());
+
+        let names = |sloid| {
+            data_storage
+                .platforms_in_sloid(sloid)
+                .iter()
+                .map(|platform| platform.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["7"], names("ch:1:sloid:1"));
+        assert_eq!(vec!["7"], names("ch:1:sloid:1:1"));
+        assert!(names("ch:1:sloid:1:2").is_empty());
+        assert!(names("ch:1:sloid:2").is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+// The k-d tree is stored implicitly: the median of each slice is the node, the elements before
+// it are the left subtree and the elements after it are the right subtree.
+// Even depths split on the latitude, odd depths split on the longitude.
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{CoordinateSystem, Coordinates, EARTH_RADIUS, Model, Platform, Stop},
+    storage::{DataStorage, ResourceStorage},
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- KdTree
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct KdPoint {
+    id: i32,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl KdPoint {
+    fn axis(&self, depth: usize) -> f64 {
+        if depth.is_multiple_of(2) {
+            self.latitude
+        } else {
+            self.longitude
+        }
+    }
+}
+
+/// A 2-d tree over WGS84 coordinates. The distances are great-circle distances in meters.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KdTree {
+    points: Vec<KdPoint>,
+}
+
+impl KdTree {
+    /// Builds the tree from (id, latitude, longitude) tuples.
+    pub fn new(points: Vec<(i32, f64, f64)>) -> Self {
+        let mut points: Vec<KdPoint> = points
+            .into_iter()
+            .map(|(id, latitude, longitude)| KdPoint {
+                id,
+                latitude,
+                longitude,
+            })
+            .collect();
+        // Sorted first to make the tree deterministic.
+        points.sort_by_key(|point| point.id);
+        build(&mut points, 0);
+
+        Self { points }
+    }
+
+    // Getters/Setters
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // Functions
+
+    /// Returns the k nearest points as (id, distance) tuples, sorted by distance.
+    pub fn nearest(&self, latitude: f64, longitude: f64, k: usize) -> Vec<(i32, f64)> {
+        let mut result = Vec::with_capacity(k + 1);
+        if k > 0 {
+            nearest(&self.points, 0, (latitude, longitude), k, &mut result);
+        }
+        result
+    }
+
+    /// Returns the points located at most radius meters away as (id, distance) tuples, sorted by distance.
+    pub fn within(&self, latitude: f64, longitude: f64, radius: f64) -> Vec<(i32, f64)> {
+        let mut result = Vec::new();
+        within(&self.points, 0, (latitude, longitude), radius, &mut result);
+        result.sort_by(compare);
+        result
+    }
+
+    /// Returns the IDs of the points inside the bounding box (inclusive), sorted by ID.
+    pub fn in_bbox(
+        &self,
+        min_latitude: f64,
+        min_longitude: f64,
+        max_latitude: f64,
+        max_longitude: f64,
+    ) -> Vec<i32> {
+        let mut result = Vec::new();
+        in_bbox(
+            &self.points,
+            0,
+            [min_latitude, min_longitude],
+            [max_latitude, max_longitude],
+            &mut result,
+        );
+        result.sort();
+        result
+    }
+}
+
+fn build(points: &mut [KdPoint], depth: usize) {
+    if points.len() <= 1 {
+        return;
+    }
+
+    let mid = points.len() / 2;
+    points.select_nth_unstable_by(mid, |a, b| a.axis(depth).total_cmp(&b.axis(depth)));
+
+    let (left, right) = points.split_at_mut(mid);
+    build(left, depth + 1);
+    build(&mut right[1..], depth + 1);
+}
+
+fn nearest(
+    points: &[KdPoint],
+    depth: usize,
+    target: (f64, f64),
+    k: usize,
+    result: &mut Vec<(i32, f64)>,
+) {
+    if points.is_empty() {
+        return;
+    }
+
+    let mid = points.len() / 2;
+    let point = points[mid];
+
+    let candidate = (
+        point.id,
+        distance(target, (point.latitude, point.longitude)),
+    );
+    let index = result.partition_point(|other| compare(other, &candidate) == Ordering::Less);
+    if index < k {
+        result.insert(index, candidate);
+        result.truncate(k);
+    }
+
+    let (near, far) = split(points, depth, target, &point);
+    nearest(near, depth + 1, target, k, result);
+
+    let bound = lower_bound(depth, target, &point);
+    if result.len() < k || result.last().is_some_and(|&(_, d)| bound <= d) {
+        nearest(far, depth + 1, target, k, result);
+    }
+}
+
+fn within(
+    points: &[KdPoint],
+    depth: usize,
+    target: (f64, f64),
+    radius: f64,
+    result: &mut Vec<(i32, f64)>,
+) {
+    if points.is_empty() {
+        return;
+    }
+
+    let mid = points.len() / 2;
+    let point = points[mid];
+
+    let d = distance(target, (point.latitude, point.longitude));
+    if d <= radius {
+        result.push((point.id, d));
+    }
+
+    let (near, far) = split(points, depth, target, &point);
+    within(near, depth + 1, target, radius, result);
+
+    if lower_bound(depth, target, &point) <= radius {
+        within(far, depth + 1, target, radius, result);
+    }
+}
+
+fn in_bbox(points: &[KdPoint], depth: usize, min: [f64; 2], max: [f64; 2], result: &mut Vec<i32>) {
+    if points.is_empty() {
+        return;
+    }
+
+    let mid = points.len() / 2;
+    let point = points[mid];
+
+    if (min[0]..=max[0]).contains(&point.latitude) && (min[1]..=max[1]).contains(&point.longitude) {
+        result.push(point.id);
+    }
+
+    let value = point.axis(depth);
+    if min[depth % 2] <= value {
+        in_bbox(&points[..mid], depth + 1, min, max, result);
+    }
+    if value <= max[depth % 2] {
+        in_bbox(&points[mid + 1..], depth + 1, min, max, result);
+    }
+}
+
+/// Returns the (near, far) subtrees of the node, relative to the target.
+fn split<'a>(
+    points: &'a [KdPoint],
+    depth: usize,
+    target: (f64, f64),
+    point: &KdPoint,
+) -> (&'a [KdPoint], &'a [KdPoint]) {
+    let mid = points.len() / 2;
+    let target_value = if depth.is_multiple_of(2) {
+        target.0
+    } else {
+        target.1
+    };
+
+    if target_value < point.axis(depth) {
+        (&points[..mid], &points[mid + 1..])
+    } else {
+        (&points[mid + 1..], &points[..mid])
+    }
+}
+
+/// Returns a lower bound of the distance between the target and any point on the other side of the splitting line.
+fn lower_bound(depth: usize, target: (f64, f64), point: &KdPoint) -> f64 {
+    if depth.is_multiple_of(2) {
+        // Distance along the meridian to the splitting parallel.
+        EARTH_RADIUS * (target.0 - point.latitude).abs().to_radians()
+    } else {
+        // Distance to the great circle of the splitting meridian.
+        let delta_longitude = (target.1 - point.longitude).abs().to_radians();
+        if delta_longitude >= std::f64::consts::FRAC_PI_2 {
+            return 0.0;
+        }
+        EARTH_RADIUS * (target.0.to_radians().cos() * delta_longitude.sin()).asin()
+    }
+}
+
+fn compare(a: &(i32, f64), b: &(i32, f64)) -> Ordering {
+    a.1.total_cmp(&b.1).then(a.0.cmp(&b.0))
+}
+
+/// Haversine distance in meters between two (latitude, longitude) points.
+fn distance((latitude_1, longitude_1): (f64, f64), (latitude_2, longitude_2): (f64, f64)) -> f64 {
+    Coordinates::new(CoordinateSystem::WGS84, latitude_1, longitude_1).haversine_distance(
+        &Coordinates::new(CoordinateSystem::WGS84, latitude_2, longitude_2),
+    )
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the k stops nearest to the point, with their distance in meters, sorted by distance.
+    pub fn nearest_stops(&self, latitude: f64, longitude: f64, k: usize) -> Vec<(&Stop, f64)> {
+        resolve_with_distance(
+            self.stops(),
+            self.stops_kd_tree().nearest(latitude, longitude, k),
+        )
+    }
+
+    /// Returns the stops located at most radius meters away from the point, with their distance, sorted by distance.
+    pub fn stops_within(&self, latitude: f64, longitude: f64, radius: f64) -> Vec<(&Stop, f64)> {
+        resolve_with_distance(
+            self.stops(),
+            self.stops_kd_tree().within(latitude, longitude, radius),
+        )
+    }
+
+    /// Returns the stops inside the bounding box, sorted by ID.
+    pub fn stops_in_bbox(
+        &self,
+        min_latitude: f64,
+        min_longitude: f64,
+        max_latitude: f64,
+        max_longitude: f64,
+    ) -> Vec<&Stop> {
+        self.stops_kd_tree()
+            .in_bbox(min_latitude, min_longitude, max_latitude, max_longitude)
+            .into_iter()
+            .filter_map(|id| self.stops().find(id))
+            .collect()
+    }
+
+    /// Returns the k platforms nearest to the point, with their distance in meters, sorted by distance.
+    pub fn nearest_platforms(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        k: usize,
+    ) -> Vec<(&Platform, f64)> {
+        resolve_with_distance(
+            self.platforms(),
+            self.platforms_kd_tree().nearest(latitude, longitude, k),
+        )
+    }
+
+    /// Returns the platforms located at most radius meters away from the point, with their distance, sorted by distance.
+    pub fn platforms_within(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    ) -> Vec<(&Platform, f64)> {
+        resolve_with_distance(
+            self.platforms(),
+            self.platforms_kd_tree().within(latitude, longitude, radius),
+        )
+    }
+
+    /// Returns the platforms inside the bounding box, sorted by ID.
+    pub fn platforms_in_bbox(
+        &self,
+        min_latitude: f64,
+        min_longitude: f64,
+        max_latitude: f64,
+        max_longitude: f64,
+    ) -> Vec<&Platform> {
+        self.platforms_kd_tree()
+            .in_bbox(min_latitude, min_longitude, max_latitude, max_longitude)
+            .into_iter()
+            .filter_map(|id| self.platforms().find(id))
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+fn resolve_with_distance<M: Model<M, K = i32>>(
+    storage: &ResourceStorage<M>,
+    points: Vec<(i32, f64)>,
+) -> Vec<(&M, f64)> {
+    points
+        .into_iter()
+        .filter_map(|(id, distance)| storage.find(id).map(|model| (model, distance)))
+        .collect()
+}
+
+/// Stops without WGS84 coordinates are not indexed.
+pub(crate) fn create_stops_kd_tree(stops: &ResourceStorage<Stop>) -> KdTree {
+    KdTree::new(
+        stops
+            .entries()
+            .into_iter()
+            .filter_map(|stop| {
+                let coordinates = stop.wgs84_coordinates()?;
+                Some((stop.id(), coordinates.latitude()?, coordinates.longitude()?))
+            })
+            .collect(),
+    )
+}
+
+/// Platforms without coordinates are not indexed.
+pub(crate) fn create_platforms_kd_tree(platforms: &ResourceStorage<Platform>) -> KdTree {
+    KdTree::new(
+        platforms
+            .entries()
+            .into_iter()
+            .filter_map(|platform| {
+                let coordinates = platform.wgs84_coordinates()?;
+                Some((
+                    platform.id(),
+                    coordinates.latitude()?,
+                    coordinates.longitude()?,
+                ))
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    const LATITUDE: f64 = 46.915;
+    const LONGITUDE: f64 = 7.415;
+
+    /// Returns all the indexed points as (id, distance) tuples, sorted by distance, without using the k-d tree.
+    fn brute_force(points: Vec<(i32, f64, f64)>) -> Vec<(i32, f64)> {
+        let mut result: Vec<_> = points
+            .into_iter()
+            .map(|(id, latitude, longitude)| {
+                (id, distance((LATITUDE, LONGITUDE), (latitude, longitude)))
+            })
+            .collect();
+        result.sort_by(compare);
+        result
+    }
+
+    fn fixture_stops() -> Vec<(i32, f64, f64)> {
+        fixture_data_storage()
+            .stops()
+            .entries()
+            .into_iter()
+            .filter_map(|stop| {
+                let coordinates = stop.wgs84_coordinates()?;
+                Some((stop.id(), coordinates.latitude()?, coordinates.longitude()?))
+            })
+            .collect()
+    }
+
+    fn fixture_platforms() -> Vec<(i32, f64, f64)> {
+        fixture_data_storage()
+            .platforms()
+            .entries()
+            .into_iter()
+            .filter_map(|platform| {
+                let coordinates = platform.wgs84_coordinates()?;
+                Some((
+                    platform.id(),
+                    coordinates.latitude()?,
+                    coordinates.longitude()?,
+                ))
+            })
+            .collect()
+    }
+
+    fn summarize<M: Model<M, K = i32>>(result: Vec<(&M, f64)>) -> Vec<(i32, f64)> {
+        result
+            .into_iter()
+            .map(|(model, distance)| (model.id(), distance))
+            .collect()
+    }
+
+    #[test]
+    fn nearest_stops() {
+        let data_storage = fixture_data_storage();
+        let expected = brute_force(fixture_stops());
+        assert_eq!(expected.len(), 5);
+
+        assert_eq!(
+            summarize(data_storage.nearest_stops(LATITUDE, LONGITUDE, 2)),
+            expected[..2]
+        );
+        // More stops are requested than there are.
+        assert_eq!(
+            summarize(data_storage.nearest_stops(LATITUDE, LONGITUDE, 10)),
+            expected
+        );
+        assert!(
+            data_storage
+                .nearest_stops(LATITUDE, LONGITUDE, 0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn stops_within() {
+        let data_storage = fixture_data_storage();
+        let radius = 2500.0;
+        let expected: Vec<_> = brute_force(fixture_stops())
+            .into_iter()
+            .filter(|(_, distance)| *distance <= radius)
+            .collect();
+        assert_eq!(expected.len(), 4);
+
+        assert_eq!(
+            summarize(data_storage.stops_within(LATITUDE, LONGITUDE, radius)),
+            expected
+        );
+        assert!(
+            data_storage
+                .stops_within(LATITUDE, LONGITUDE, 10.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn stops_in_bbox() {
+        let data_storage = fixture_data_storage();
+        let (min, max) = ((46.905, 7.405), (46.935, 7.435));
+        let mut expected: Vec<_> = fixture_stops()
+            .into_iter()
+            .filter(|&(_, latitude, longitude)| {
+                (min.0..=max.0).contains(&latitude) && (min.1..=max.1).contains(&longitude)
+            })
+            .map(|(id, _, _)| id)
+            .collect();
+        expected.sort();
+        assert_eq!(expected, vec![8500002, 8500003, 8500004]);
+
+        let stop_ids: Vec<_> = data_storage
+            .stops_in_bbox(min.0, min.1, max.0, max.1)
+            .into_iter()
+            .map(|stop| stop.id())
+            .collect();
+        assert_eq!(stop_ids, expected);
+        // The bounds are inclusive.
+        assert_eq!(
+            data_storage
+                .stops_in_bbox(46.91, 7.41, 46.91, 7.41)
+                .into_iter()
+                .map(|stop| stop.id())
+                .collect::<Vec<_>>(),
+            vec![8500002]
+        );
+    }
+
+    #[test]
+    fn platforms() {
+        let data_storage = fixture_data_storage();
+        let expected = brute_force(fixture_platforms());
+        assert_eq!(expected.len(), 2);
+
+        assert_eq!(
+            summarize(data_storage.nearest_platforms(LATITUDE, LONGITUDE, 1)),
+            expected[..1]
+        );
+        assert_eq!(
+            summarize(data_storage.nearest_platforms(LATITUDE, LONGITUDE, 5)),
+            expected
+        );
+        assert!(
+            data_storage
+                .nearest_platforms(LATITUDE, LONGITUDE, 0)
+                .is_empty()
+        );
+
+        let radius = (expected[0].1 + expected[1].1) / 2.0;
+        assert_eq!(
+            summarize(data_storage.platforms_within(LATITUDE, LONGITUDE, radius)),
+            expected[..1]
+        );
+
+        let platform_ids: Vec<_> = data_storage
+            .platforms_in_bbox(46.9, 7.4, 46.91, 7.41)
+            .into_iter()
+            .map(|platform| platform.id())
+            .collect();
+        assert_eq!(platform_ids, vec![1]);
+    }
+}
@@ -1,16 +1,21 @@
-use chrono::{Days, NaiveDate};
+use chrono::NaiveDate;
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     JourneyId, Result,
+    calendar::ServiceCalendar,
     models::{
-        Attribute, BitField, Direction, ExchangeTimeAdministration, ExchangeTimeJourney,
-        ExchangeTimeLine, Holiday, InformationText, Journey, JourneyPlatform, Line, Model,
-        Platform, Stop, StopConnection, ThroughService, TimetableMetadataEntry, TransportCompany,
-        TransportType, Version,
+        Attribute, BitField, BorderPoint, Direction, ExchangeTimeAdministration,
+        ExchangeTimeJourney, ExchangeTimeLine, Holiday, InfoTextCode, InformationText, Journey,
+        JourneyMetadataType, JourneyPlatform, Line, Model, Platform, Stop, StopConnection,
+        StopGroup, ThroughService, TimetableMetadataEntry, TransportCompany, TransportType,
+        Version,
     },
     parsing,
+    search::StopSearchIndex,
+    sloid::{create_platforms_by_sloid, create_platforms_by_stop_sloid, create_stops_by_sloid},
+    spatial::{KdTree, create_platforms_kd_tree, create_stops_kd_tree},
     utils::{count_days_between_two_dates, timetable_end_date, timetable_start_date},
 };
 
@@ -36,6 +41,8 @@ pub struct DataStorage {
     // Stop data
     stops: ResourceStorage<Stop>,
     stop_connections: ResourceStorage<StopConnection>,
+    stop_groups: ResourceStorage<StopGroup>,
+    border_points: ResourceStorage<BorderPoint>,
 
     // Timetable data
     journeys: ResourceStorage<Journey>,
@@ -50,16 +57,39 @@ pub struct DataStorage {
 
     // Maps
     bit_fields_by_day: FxHashMap<NaiveDate, FxHashSet<i32>>,
+    holidays_by_date: FxHashMap<NaiveDate, i32>,
     bit_fields_by_stop_id: FxHashMap<i32, FxHashSet<i32>>,
     journeys_by_stop_id_and_bit_field_id: FxHashMap<(i32, i32), Vec<i32>>,
+    journeys_by_bit_field_id: FxHashMap<i32, Vec<i32>>,
+    journeys_by_border_stop_id: FxHashMap<i32, Vec<i32>>,
     stop_connections_by_stop_id: FxHashMap<i32, FxHashSet<i32>>,
+    stop_groups_by_stop_id: FxHashMap<i32, Vec<i32>>,
     bit_field_id_for_through_service_by_journey_id_stop_id:
         FxHashMap<(JourneyId, JourneyId, i32), i32>,
     exchange_times_administration_map: FxHashMap<(Option<i32>, String, String), i32>,
     exchange_times_journey_map: FxHashMap<(i32, JourneyId, JourneyId), FxHashSet<i32>>,
+    exchange_times_line_by_stop_id: FxHashMap<Option<i32>, Vec<i32>>,
+    journey_platforms_by_journey_id: FxHashMap<JourneyId, Vec<i32>>,
+    journeys_by_journey_id: FxHashMap<JourneyId, i32>,
+    journeys_by_sjyid: FxHashMap<String, i32>,
+    through_services_by_journey_id: FxHashMap<JourneyId, Vec<i32>>,
+    through_services_by_next_journey_id: FxHashMap<JourneyId, Vec<i32>>,
+    attributes_by_designation: FxHashMap<String, i32>,
+    directions_by_legacy_id: FxHashMap<String, i32>,
+    transport_companies_by_administration: FxHashMap<String, i32>,
+    transport_types_by_designation: FxHashMap<String, i32>,
+    stops_by_sloid: FxHashMap<String, i32>,
+    platforms_by_sloid: FxHashMap<String, i32>,
+    platforms_by_stop_sloid: FxHashMap<String, Vec<i32>>,
+    // Spatial indexes
+    stops_kd_tree: KdTree,
+    platforms_kd_tree: KdTree,
+    // Search indexes
+    stop_search_index: StopSearchIndex,
 
     // Additional global data
     default_exchange_time: (i16, i16), // (InterCity exchange time, Exchange time for all other journey types)
+    max_trip_days: usize,
 }
 
 impl DataStorage {
@@ -79,8 +109,10 @@ impl DataStorage {
             parsing::load_transport_types(version, path)?;
 
         // Stop data
-        let stop_connections = parsing::load_stop_connections(path, &attributes_pk_type_converter)?;
+        let (stop_connections, stop_groups) =
+            parsing::load_stop_connections(path, &attributes_pk_type_converter)?;
         let (stops, default_exchange_time) = parsing::load_stops(version, path)?;
+        let border_points = parsing::load_border_points(path)?;
 
         // Timetable data
         let (journeys, journeys_pk_type_converter) = parsing::load_journeys(
@@ -102,22 +134,63 @@ impl DataStorage {
 
         log::info!("Building bit_fields_by_day...");
         let bit_fields_by_day = create_bit_fields_by_day(&bit_fields, &timetable_metadata)?;
+        log::info!("Building holidays by date...");
+        let holidays_by_date = create_holidays_by_date(&holidays);
         log::info!("Building bit_fields_by_stop_id...");
         let bit_fields_by_stop_id = create_bit_fields_by_stop_id(&journeys);
         log::info!("Building journeys by stop id and bit field_id...");
         let journeys_by_stop_id_and_bit_field_id =
             create_journeys_by_stop_id_and_bit_field_id(&journeys);
+        log::info!("Building journeys by bit field id...");
+        let journeys_by_bit_field_id = create_journeys_by_bit_field_id(&journeys);
+        log::info!("Building journeys by border stop id...");
+        let journeys_by_border_stop_id =
+            create_journeys_by_border_stop_id(&journeys, &border_points);
         log::info!("Building stop connections by stop id...");
         let bit_field_id_for_through_service_by_journey_id_stop_id =
             create_bit_field_id_through_service_by_journey_id_stop_id(&through_service);
         log::info!("Building stop connections by stop id...");
         let stop_connections_by_stop_id = create_stop_connections_by_stop_id(&stop_connections);
+        log::info!("Building stop groups by stop id...");
+        let stop_groups_by_stop_id = create_stop_groups_by_stop_id(&stop_groups);
         log::info!("Building exchange times administration map...");
         let exchange_times_administration_map =
             create_exchange_times_administration_map(&exchange_times_administration);
         log::info!("Building exchange times journey_map...");
         let exchange_times_journey_map = create_exchange_times_journey_map(&exchange_times_journey);
-        log::info!("Building through service map...");
+        log::info!("Building exchange times line by stop id...");
+        let exchange_times_line_by_stop_id =
+            create_exchange_times_line_by_stop_id(&exchange_times_line);
+        log::info!("Building journey platforms by journey id...");
+        let journey_platforms_by_journey_id =
+            create_journey_platforms_by_journey_id(&journey_platform);
+        log::info!("Building journeys by journey id...");
+        let journeys_by_journey_id = create_journeys_by_journey_id(&journeys);
+        log::info!("Building journeys by sjyid...");
+        let journeys_by_sjyid = create_journeys_by_sjyid(&journeys, &information_texts);
+        log::info!("Building through services by journey id...");
+        let through_services_by_journey_id =
+            create_through_services_by_journey_id(&through_service);
+        log::info!("Building through services by next journey id...");
+        let through_services_by_next_journey_id =
+            create_through_services_by_next_journey_id(&through_service);
+        log::info!("Building transport companies by administration...");
+        let transport_companies_by_administration =
+            create_transport_companies_by_administration(&transport_companies);
+        log::info!("Building stops by sloid...");
+        let stops_by_sloid = create_stops_by_sloid(&stops);
+        log::info!("Building platforms by sloid...");
+        let platforms_by_sloid = create_platforms_by_sloid(&platforms);
+        log::info!("Building platforms by stop sloid...");
+        let platforms_by_stop_sloid = create_platforms_by_stop_sloid(&platforms);
+        log::info!("Building stops k-d tree...");
+        let stops_kd_tree = create_stops_kd_tree(&stops);
+        log::info!("Building platforms k-d tree...");
+        let platforms_kd_tree = create_platforms_kd_tree(&platforms);
+        log::info!("Building stop search index...");
+        let stop_search_index = StopSearchIndex::new(&stops);
+        log::info!("Computing max trip days...");
+        let max_trip_days = compute_max_trip_days(&journeys);
 
         let data_storage = Self {
             // Time-relevant data
@@ -133,7 +206,9 @@ impl DataStorage {
             transport_types,
             // Stop data
             stop_connections,
+            stop_groups,
             stops,
+            border_points,
             // Timetable data
             journeys,
             journey_platform,
@@ -145,14 +220,37 @@ impl DataStorage {
             exchange_times_line,
             // Maps
             bit_fields_by_day,
+            holidays_by_date,
             bit_fields_by_stop_id,
             journeys_by_stop_id_and_bit_field_id,
+            journeys_by_bit_field_id,
+            journeys_by_border_stop_id,
             stop_connections_by_stop_id,
+            stop_groups_by_stop_id,
             bit_field_id_for_through_service_by_journey_id_stop_id,
             exchange_times_administration_map,
             exchange_times_journey_map,
+            exchange_times_line_by_stop_id,
+            journey_platforms_by_journey_id,
+            journeys_by_journey_id,
+            journeys_by_sjyid,
+            through_services_by_journey_id,
+            through_services_by_next_journey_id,
+            attributes_by_designation: attributes_pk_type_converter,
+            directions_by_legacy_id: directions_pk_type_converter,
+            transport_companies_by_administration,
+            transport_types_by_designation: transport_types_pk_type_converter,
+            stops_by_sloid,
+            platforms_by_sloid,
+            platforms_by_stop_sloid,
+            // Spatial indexes
+            stops_kd_tree,
+            platforms_kd_tree,
+            // Search indexes
+            stop_search_index,
             // Additional global data
             default_exchange_time,
+            max_trip_days,
         };
 
         Ok(data_storage)
@@ -164,10 +262,30 @@ impl DataStorage {
         &self.bit_fields
     }
 
+    pub fn holidays(&self) -> &ResourceStorage<Holiday> {
+        &self.holidays
+    }
+
+    pub fn attributes(&self) -> &ResourceStorage<Attribute> {
+        &self.attributes
+    }
+
+    pub fn information_texts(&self) -> &ResourceStorage<InformationText> {
+        &self.information_texts
+    }
+
+    pub fn directions(&self) -> &ResourceStorage<Direction> {
+        &self.directions
+    }
+
     pub fn journeys(&self) -> &ResourceStorage<Journey> {
         &self.journeys
     }
 
+    pub fn journey_platform(&self) -> &ResourceStorage<JourneyPlatform> {
+        &self.journey_platform
+    }
+
     pub fn lines(&self) -> &ResourceStorage<Line> {
         &self.lines
     }
@@ -184,10 +302,22 @@ impl DataStorage {
         &self.through_service
     }
 
+    pub fn stop_groups(&self) -> &ResourceStorage<StopGroup> {
+        &self.stop_groups
+    }
+
     pub fn stops(&self) -> &ResourceStorage<Stop> {
         &self.stops
     }
 
+    pub fn border_points(&self) -> &ResourceStorage<BorderPoint> {
+        &self.border_points
+    }
+
+    pub fn transport_companies(&self) -> &ResourceStorage<TransportCompany> {
+        &self.transport_companies
+    }
+
     pub fn transport_types(&self) -> &ResourceStorage<TransportType> {
         &self.transport_types
     }
@@ -212,6 +342,10 @@ impl DataStorage {
         &self.bit_fields_by_day
     }
 
+    pub fn holidays_by_date(&self) -> &FxHashMap<NaiveDate, i32> {
+        &self.holidays_by_date
+    }
+
     pub fn bit_fields_by_stop_id(&self) -> &FxHashMap<i32, FxHashSet<i32>> {
         &self.bit_fields_by_stop_id
     }
@@ -220,10 +354,24 @@ impl DataStorage {
         &self.journeys_by_stop_id_and_bit_field_id
     }
 
+    /// The journeys are indexed by the bit fields of all their sections (*A VE), sorted by ID.
+    pub fn journeys_by_bit_field_id(&self) -> &FxHashMap<i32, Vec<i32>> {
+        &self.journeys_by_bit_field_id
+    }
+
+    /// The journeys are indexed by the stops where they cross a border (GRENZHLT), sorted by ID.
+    pub fn journeys_by_border_stop_id(&self) -> &FxHashMap<i32, Vec<i32>> {
+        &self.journeys_by_border_stop_id
+    }
+
     pub fn stop_connections_by_stop_id(&self) -> &FxHashMap<i32, FxHashSet<i32>> {
         &self.stop_connections_by_stop_id
     }
 
+    pub fn stop_groups_by_stop_id(&self) -> &FxHashMap<i32, Vec<i32>> {
+        &self.stop_groups_by_stop_id
+    }
+
     pub fn bit_field_id_for_through_service_by_journey_id_stop_id(
         &self,
     ) -> &FxHashMap<(JourneyId, JourneyId, i32), i32> {
@@ -242,9 +390,246 @@ impl DataStorage {
         &self.exchange_times_journey_map
     }
 
+    /// A None key contains the exchange times that apply to all stops.
+    pub fn exchange_times_line_by_stop_id(&self) -> &FxHashMap<Option<i32>, Vec<i32>> {
+        &self.exchange_times_line_by_stop_id
+    }
+
+    pub fn journey_platforms_by_journey_id(&self) -> &FxHashMap<JourneyId, Vec<i32>> {
+        &self.journey_platforms_by_journey_id
+    }
+
+    pub fn journeys_by_journey_id(&self) -> &FxHashMap<JourneyId, i32> {
+        &self.journeys_by_journey_id
+    }
+
+    pub fn journeys_by_sjyid(&self) -> &FxHashMap<String, i32> {
+        &self.journeys_by_sjyid
+    }
+
+    pub fn through_services_by_journey_id(&self) -> &FxHashMap<JourneyId, Vec<i32>> {
+        &self.through_services_by_journey_id
+    }
+
+    pub fn through_services_by_next_journey_id(&self) -> &FxHashMap<JourneyId, Vec<i32>> {
+        &self.through_services_by_next_journey_id
+    }
+
+    pub fn attributes_by_designation(&self) -> &FxHashMap<String, i32> {
+        &self.attributes_by_designation
+    }
+
+    pub fn directions_by_legacy_id(&self) -> &FxHashMap<String, i32> {
+        &self.directions_by_legacy_id
+    }
+
+    pub fn transport_companies_by_administration(&self) -> &FxHashMap<String, i32> {
+        &self.transport_companies_by_administration
+    }
+
+    pub fn transport_types_by_designation(&self) -> &FxHashMap<String, i32> {
+        &self.transport_types_by_designation
+    }
+
+    pub fn stops_by_sloid(&self) -> &FxHashMap<String, i32> {
+        &self.stops_by_sloid
+    }
+
+    pub fn platforms_by_sloid(&self) -> &FxHashMap<String, i32> {
+        &self.platforms_by_sloid
+    }
+
+    pub fn platforms_by_stop_sloid(&self) -> &FxHashMap<String, Vec<i32>> {
+        &self.platforms_by_stop_sloid
+    }
+
+    pub fn stops_kd_tree(&self) -> &KdTree {
+        &self.stops_kd_tree
+    }
+
+    pub fn platforms_kd_tree(&self) -> &KdTree {
+        &self.platforms_kd_tree
+    }
+
+    pub fn stop_search_index(&self) -> &StopSearchIndex {
+        &self.stop_search_index
+    }
+
     pub fn default_exchange_time(&self) -> (i16, i16) {
         self.default_exchange_time
     }
+
+    /// The maximum number of days between the service date of a journey and its arrival at its last stop.
+    pub fn max_trip_days(&self) -> usize {
+        self.max_trip_days
+    }
+
+    // Functions
+
+    /// Returns true if the bit field is active on the given date.
+    /// A None value or a value of 0 means that it is active every day of the timetable period.
+    pub fn is_bit_field_active(&self, bit_field_id: Option<i32>, date: NaiveDate) -> bool {
+        self.bit_fields_by_day
+            .get(&date)
+            .is_some_and(|bit_field_ids| bit_field_ids.contains(&bit_field_id.unwrap_or(0)))
+    }
+
+    /// Returns the journeys crossing a border (GRENZHLT), sorted by ID.
+    pub fn journeys_crossing_border(&self) -> Vec<&Journey> {
+        let mut journey_ids: Vec<i32> = self
+            .journeys_by_border_stop_id()
+            .values()
+            .flatten()
+            .copied()
+            .collect();
+        journey_ids.sort();
+        journey_ids.dedup();
+        journey_ids
+            .into_iter()
+            .filter_map(|id| self.journeys().find(id))
+            .collect()
+    }
+
+    /// Returns the journeys crossing the border at the stop, sorted by ID.
+    pub fn journeys_crossing_border_at(&self, stop_id: i32) -> Vec<&Journey> {
+        self.journeys_by_border_stop_id()
+            .get(&stop_id)
+            .into_iter()
+            .flatten()
+            .filter_map(|&id| self.journeys().find(id))
+            .collect()
+    }
+
+    /// Returns the platform of each visit of the journey at the stop, in route order, on the date on which the journey leaves its first stop.
+    /// A journey serving the stop several times (e.g. a loop) has one entry per visit, None if the platform of the visit is unknown.
+    pub fn platform_of(
+        &self,
+        journey_id: i32,
+        stop_id: i32,
+        date: NaiveDate,
+    ) -> Vec<Option<&Platform>> {
+        let Some(journey) = self.journeys().find(journey_id) else {
+            return Vec::new();
+        };
+        journey
+            .route()
+            .iter()
+            .enumerate()
+            .filter(|(_, route_entry)| route_entry.stop_id() == stop_id)
+            .map(|(route_index, _)| self.platform_at(journey, route_index, date))
+            .collect()
+    }
+
+    /// Returns the platform of the journey at the route entry, on the date on which the journey leaves its first stop.
+    /// Only the GLEIS entries whose bit field is active on the date are considered.
+    /// An entry with a time matching the arrival or departure time at the route entry takes precedence over an entry without time.
+    /// An entry whose time does not match is only used if the journey serves the stop once.
+    pub fn platform_at(
+        &self,
+        journey: &Journey,
+        route_index: usize,
+        date: NaiveDate,
+    ) -> Option<&Platform> {
+        let route_entry = journey.route().get(route_index)?;
+        let stop_id = route_entry.stop_id();
+
+        let candidates: Vec<(&JourneyPlatform, &Platform)> = self
+            .journey_platforms_by_journey_id()
+            .get(&journey.journey_id())?
+            .iter()
+            .filter_map(|&id| self.journey_platform().find(id))
+            .filter(|journey_platform| {
+                self.is_bit_field_active(journey_platform.bit_field_id(), date)
+            })
+            .filter_map(|journey_platform| {
+                let platform = self.platforms().find(journey_platform.platform_id())?;
+                (platform.stop_id() == stop_id).then_some((journey_platform, platform))
+            })
+            .collect();
+
+        let times = [*route_entry.arrival_time(), *route_entry.departure_time()];
+        candidates
+            .iter()
+            .find(|(journey_platform, _)| {
+                journey_platform
+                    .time()
+                    .is_some_and(|time| times.contains(&Some(time)))
+            })
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|(journey_platform, _)| journey_platform.time().is_none())
+            })
+            .or_else(|| {
+                // The time cannot be wrong if the journey serves the stop only once.
+                let visits = journey
+                    .route()
+                    .iter()
+                    .filter(|route_entry| route_entry.stop_id() == stop_id)
+                    .count();
+                candidates.first().filter(|_| visits == 1)
+            })
+            .map(|(_, platform)| *platform)
+    }
+
+    /// Returns the stop itself and, if the ID is the one of a stop group, the members of the group, sorted by ID.
+    /// The stop itself is omitted if it is not listed in BAHNHOF.
+    pub fn resolve_stop_ids(&self, stop_id: i32) -> Vec<i32> {
+        let mut stop_ids: Vec<i32> = self
+            .stops
+            .find(stop_id)
+            .map(|stop| stop.id())
+            .into_iter()
+            .chain(
+                self.stop_groups
+                    .find(stop_id)
+                    .into_iter()
+                    .flat_map(|stop_group| stop_group.stop_ids().iter().copied()),
+            )
+            .collect();
+        stop_ids.sort();
+        stop_ids.dedup();
+        stop_ids
+    }
+
+    /// The designation is the code used in the ATTRIBUT and FPLAN files (e.g. "WR" for the dining car).
+    pub fn attribute_by_designation(&self, designation: &str) -> Option<&Attribute> {
+        self.attributes_by_designation
+            .get(designation)
+            .and_then(|&id| self.attributes.find(id))
+    }
+
+    /// The legacy ID is the code used in the RICHTUNG and FPLAN files (e.g. "R000011").
+    pub fn direction_by_legacy_id(&self, legacy_id: &str) -> Option<&Direction> {
+        self.directions_by_legacy_id
+            .get(legacy_id)
+            .and_then(|&id| self.directions.find(id))
+    }
+
+    /// The SJYID is the Swiss Journey ID (e.g. "ch:1:sjyid:100001:3-002"), used by the real-time feeds.
+    pub fn journey_by_sjyid(&self, sjyid: &str) -> Option<&Journey> {
+        self.journeys_by_sjyid
+            .get(sjyid)
+            .and_then(|&id| self.journeys.find(id))
+    }
+
+    /// The administration is the code used in the FPLAN file (e.g. "000011" for SBB).
+    /// If several transport companies share the administration, the one with the lowest ID is returned.
+    pub fn transport_company_by_administration(
+        &self,
+        administration: &str,
+    ) -> Option<&TransportCompany> {
+        self.transport_companies_by_administration
+            .get(administration)
+            .and_then(|&id| self.transport_companies.find(id))
+    }
+
+    /// The designation is the code used in the ZUGART and FPLAN files (e.g. "IC").
+    pub fn transport_type_by_designation(&self, designation: &str) -> Option<&TransportType> {
+        self.transport_types_by_designation
+            .get(designation)
+            .and_then(|&id| self.transport_types.find(id))
+    }
 }
 
 // ------------------------------------------------------------------------------------------------
@@ -291,44 +676,38 @@ fn create_bit_fields_by_day(
     let num_days =
         count_days_between_two_dates(start_date, timetable_end_date(timetable_metadata)?);
 
-    let dates: Vec<NaiveDate> = (0..num_days)
-        .map(|i| {
-            start_date
-                // unwrap: Converting i from usize to u64 will never fail.
-                .checked_add_days(Days::new(i.try_into().unwrap()))
-                // unwrap: Adding days will never fail.
-                .unwrap()
-        })
+    // The bit field 0 is active every day.
+    let map = ServiceCalendar::full(start_date, num_days)
+        .dates()
+        .into_iter()
+        .map(|date| (date, FxHashSet::from_iter([0])))
         .collect();
 
-    let mut map = FxHashMap::default();
-    dates.iter().for_each(|date| {
-        map.entry(*date).or_insert(FxHashSet::default()).insert(0);
-    });
-
-    let result = bit_fields.data().keys().fold(map, |mut acc, bit_field_id| {
-        let bit_field = bit_fields
-            .find(*bit_field_id)
-            .unwrap_or_else(|| panic!("Bitfield id {:?} not found.", bit_field_id));
-        let indexes: Vec<usize> = bit_field
-            .bits()
-            .iter()
-            // The first two bits must be ignored.
-            .skip(2)
-            .enumerate()
-            .filter(|&(ref i, &x)| *i < num_days && x == 1)
-            .map(|(i, _)| i)
-            .collect();
-
-        indexes.iter().for_each(|&i| {
-            acc.entry(dates[i]).or_default().insert(bit_field.id());
-        });
-
-        acc
-    });
+    let result = bit_fields.entries().into_iter().fold(
+        map,
+        |mut acc: FxHashMap<_, FxHashSet<i32>>, bit_field| {
+            for date in ServiceCalendar::from_bit_field(bit_field, start_date, num_days).dates() {
+                acc.entry(date).or_default().insert(bit_field.id());
+            }
+            acc
+        },
+    );
     Ok(result)
 }
 
+/// If several holidays have the same date, the one with the lowest id is kept.
+fn create_holidays_by_date(holidays: &ResourceStorage<Holiday>) -> FxHashMap<NaiveDate, i32> {
+    holidays
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, holiday| {
+            acc.entry(holiday.date())
+                .and_modify(|id| *id = (*id).min(holiday.id()))
+                .or_insert(holiday.id());
+            acc
+        })
+}
+
 fn create_bit_fields_by_stop_id(
     journeys: &ResourceStorage<Journey>,
 ) -> FxHashMap<i32, FxHashSet<i32>> {
@@ -336,11 +715,8 @@ fn create_bit_fields_by_stop_id(
         .entries()
         .into_iter()
         .fold(FxHashMap::default(), |mut acc, journey| {
-            journey.route().iter().for_each(|route_entry| {
-                acc.entry(route_entry.stop_id())
-                    .or_default()
-                    // If the journey has no bit_field_id, the default value is 0. A value of 0 means that the journey operates every day.
-                    .insert(journey.bit_field_id().unwrap_or(0));
+            for_each_section_stop(journey, |stop_id, bit_field_id| {
+                acc.entry(stop_id).or_default().insert(bit_field_id);
             });
             acc
         })
@@ -349,18 +725,93 @@ fn create_bit_fields_by_stop_id(
 fn create_journeys_by_stop_id_and_bit_field_id(
     journeys: &ResourceStorage<Journey>,
 ) -> FxHashMap<(i32, i32), Vec<i32>> {
-    journeys
-        .entries()
-        .into_iter()
-        .fold(FxHashMap::default(), |mut acc, journey| {
-            journey.route().iter().for_each(|route_entry| {
-                // If the journey has no bit_field_id, the default value is 0. A value of 0 means that the journey operates every day.
-                acc.entry((route_entry.stop_id(), journey.bit_field_id().unwrap_or(0)))
+    let mut journeys_by_stop_id_and_bit_field_id = journeys.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<(i32, i32), Vec<i32>>, journey| {
+            for_each_section_stop(journey, |stop_id, bit_field_id| {
+                acc.entry((stop_id, bit_field_id))
                     .or_default()
                     .push(journey.id());
             });
             acc
-        })
+        },
+    );
+    journeys_by_stop_id_and_bit_field_id
+        .values_mut()
+        .for_each(|ids| ids.dedup());
+    journeys_by_stop_id_and_bit_field_id
+}
+
+/// Calls f with the (stop ID, bit field ID) of every stop of every section (*A VE) of the journey.
+/// A section without bit field operates every day, its bit field ID is 0.
+fn for_each_section_stop<F>(journey: &Journey, mut f: F)
+where
+    F: FnMut(i32, i32),
+{
+    for entry in journey
+        .metadata()
+        .get(&JourneyMetadataType::BitField)
+        .into_iter()
+        .flatten()
+    {
+        if let Some((start, end)) = journey.section_of(entry) {
+            for route_entry in &journey.route()[start..=end] {
+                f(route_entry.stop_id(), entry.bit_field_id().unwrap_or(0));
+            }
+        }
+    }
+}
+
+fn create_journeys_by_bit_field_id(
+    journeys: &ResourceStorage<Journey>,
+) -> FxHashMap<i32, Vec<i32>> {
+    let mut journeys_by_bit_field_id = journeys.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<i32, Vec<i32>>, journey| {
+            journey
+                .metadata()
+                .get(&JourneyMetadataType::BitField)
+                .into_iter()
+                .flatten()
+                .for_each(|entry| {
+                    // A value of 0 means that the section operates every day.
+                    acc.entry(entry.bit_field_id().unwrap_or(0))
+                        .or_default()
+                        .push(journey.id());
+                });
+            acc
+        },
+    );
+    journeys_by_bit_field_id.values_mut().for_each(|ids| {
+        ids.sort();
+        ids.dedup();
+    });
+    journeys_by_bit_field_id
+}
+
+fn create_journeys_by_border_stop_id(
+    journeys: &ResourceStorage<Journey>,
+    border_points: &ResourceStorage<BorderPoint>,
+) -> FxHashMap<i32, Vec<i32>> {
+    let mut journeys_by_border_stop_id = journeys.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<i32, Vec<i32>>, journey| {
+            journey
+                .border_crossings_in(border_points)
+                .into_iter()
+                .for_each(|i| {
+                    acc.entry(journey.route()[i].stop_id())
+                        .or_default()
+                        .push(journey.id());
+                });
+            acc
+        },
+    );
+    journeys_by_border_stop_id.values_mut().for_each(|ids| {
+        ids.sort();
+        ids.dedup();
+    });
+    journeys_by_border_stop_id
 }
 
 /// Given journey_stop_id, and journey_id_1, journey_id_2, we obtain the bit_field_id of the ThroughService
@@ -422,6 +873,146 @@ fn create_exchange_times_journey_map(
     )
 }
 
+fn create_exchange_times_line_by_stop_id(
+    exchange_times_line: &ResourceStorage<ExchangeTimeLine>,
+) -> FxHashMap<Option<i32>, Vec<i32>> {
+    exchange_times_line.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc, exchange_time| {
+            acc.entry(exchange_time.stop_id())
+                .or_default()
+                .push(exchange_time.id());
+            acc
+        },
+    )
+}
+
+fn create_journey_platforms_by_journey_id(
+    journey_platform: &ResourceStorage<JourneyPlatform>,
+) -> FxHashMap<JourneyId, Vec<i32>> {
+    let mut journey_platforms_by_journey_id = journey_platform.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<JourneyId, Vec<i32>>, journey_platform| {
+            let key = (
+                journey_platform.journey_legacy_id(),
+                journey_platform.administration().to_string(),
+            );
+
+            acc.entry(key).or_default().push(journey_platform.id());
+            acc
+        },
+    );
+    // Sorted to keep the order of the file.
+    journey_platforms_by_journey_id
+        .values_mut()
+        .for_each(|ids| ids.sort());
+    journey_platforms_by_journey_id
+}
+
+fn create_journeys_by_journey_id(journeys: &ResourceStorage<Journey>) -> FxHashMap<JourneyId, i32> {
+    journeys
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, journey| {
+            acc.insert(journey.journey_id(), journey.id());
+            acc
+        })
+}
+
+/// If several journeys share a SJYID, the one with the lowest ID is kept.
+fn create_journeys_by_sjyid(
+    journeys: &ResourceStorage<Journey>,
+    information_texts: &ResourceStorage<InformationText>,
+) -> FxHashMap<String, i32> {
+    let mut journeys = journeys.entries();
+    journeys.sort_by_key(|journey| journey.id());
+
+    journeys
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, journey| {
+            let sjyids = journey
+                .metadata()
+                .get(&JourneyMetadataType::InformationText)
+                .into_iter()
+                .flatten()
+                .filter(|entry| entry.info_text_code() == Some(InfoTextCode::SwissJourneyId))
+                .filter_map(|entry| information_texts.find(entry.resource_id()?)?.value());
+            for sjyid in sjyids {
+                acc.entry(sjyid.to_owned()).or_insert(journey.id());
+            }
+            acc
+        })
+}
+
+/// The through services are indexed by the ID of the journey that is continued (journey 1).
+fn create_through_services_by_journey_id(
+    through_services: &ResourceStorage<ThroughService>,
+) -> FxHashMap<JourneyId, Vec<i32>> {
+    through_services
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, through_service| {
+            acc.entry(through_service.journey_1_id().clone())
+                .or_default()
+                .push(through_service.id());
+            acc
+        })
+}
+
+/// The through services are indexed by the journey continuing the other one (journey 2).
+fn create_through_services_by_next_journey_id(
+    through_services: &ResourceStorage<ThroughService>,
+) -> FxHashMap<JourneyId, Vec<i32>> {
+    through_services
+        .entries()
+        .into_iter()
+        .fold(FxHashMap::default(), |mut acc, through_service| {
+            acc.entry(through_service.journey_2_id().clone())
+                .or_default()
+                .push(through_service.id());
+            acc
+        })
+}
+
+/// The group IDs of each stop are sorted.
+fn create_stop_groups_by_stop_id(
+    stop_groups: &ResourceStorage<StopGroup>,
+) -> FxHashMap<i32, Vec<i32>> {
+    let mut stop_groups_by_stop_id = stop_groups.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc: FxHashMap<i32, Vec<i32>>, stop_group| {
+            stop_group.stop_ids().iter().for_each(|&stop_id| {
+                acc.entry(stop_id).or_default().push(stop_group.id());
+            });
+            acc
+        },
+    );
+    stop_groups_by_stop_id
+        .values_mut()
+        .for_each(|ids| ids.sort());
+    stop_groups_by_stop_id
+}
+
+/// If several transport companies share an administration, the one with the lowest ID is kept.
+fn create_transport_companies_by_administration(
+    transport_companies: &ResourceStorage<TransportCompany>,
+) -> FxHashMap<String, i32> {
+    transport_companies.entries().into_iter().fold(
+        FxHashMap::default(),
+        |mut acc, transport_company| {
+            transport_company
+                .administrations()
+                .iter()
+                .for_each(|administration| {
+                    acc.entry(administration.to_owned())
+                        .and_modify(|id| *id = (*id).min(transport_company.id()))
+                        .or_insert(transport_company.id());
+                });
+            acc
+        },
+    )
+}
+
 fn create_exchange_times_administration_map(
     exchange_times_administration: &ResourceStorage<ExchangeTimeAdministration>,
 ) -> FxHashMap<(Option<i32>, String, String), i32> {
@@ -439,3 +1030,199 @@ fn create_exchange_times_administration_map(
         },
     )
 }
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+fn compute_max_trip_days(journeys: &ResourceStorage<Journey>) -> usize {
+    let date = NaiveDate::default();
+    journeys
+        .entries()
+        .into_iter()
+        .filter_map(|journey| {
+            journey
+                .route_datetimes(date)
+                .into_iter()
+                .rev()
+                .find_map(|(arrival_at, departure_at)| arrival_at.or(departure_at))
+        })
+        .map(|datetime| (datetime.date() - date).num_days() as usize)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+    use crate::models::Language;
+    use pretty_assertions::assert_eq;
+
+    /// Returns the data storage of the small HRDF dataset in tests/fixtures/hrdf, loaded once.
+    pub(crate) fn fixture_data_storage() -> &'static DataStorage {
+        static DATA_STORAGE: OnceLock<DataStorage> = OnceLock::new();
+        DATA_STORAGE.get_or_init(|| {
+            let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hrdf");
+            DataStorage::new(Version::V_5_40_41_2_0_7, path).unwrap()
+        })
+    }
+
+    #[test]
+    fn lookup_indexes() {
+        let data_storage = fixture_data_storage();
+
+        assert_eq!(
+            Some("IR"),
+            data_storage
+                .transport_type_by_designation("IR")
+                .map(|transport_type| transport_type.designation())
+        );
+        assert_eq!(
+            Some("Dorf"),
+            data_storage
+                .direction_by_legacy_id("R000001")
+                .map(|direction| direction.name())
+        );
+        assert!(data_storage.attribute_by_designation("WR").is_some());
+        assert!(data_storage.attribute_by_designation("ZZ").is_none());
+        // The companies 1 and 3 share the administration 000011, the lowest ID wins.
+        assert_eq!(
+            Some(1),
+            data_storage
+                .transport_company_by_administration("000011")
+                .map(|transport_company| transport_company.id())
+        );
+        assert_eq!(
+            Some("PostAuto"),
+            data_storage
+                .transport_company_by_administration("000801")
+                .and_then(|transport_company| transport_company.long_name(Language::English))
+        );
+        assert!(
+            data_storage
+                .transport_company_by_administration("000999")
+                .is_none()
+        );
+        // The journey 8 has a section on the bit field 1 and another one on the bit field 3.
+        assert_eq!(
+            Some(&vec![8]),
+            data_storage.journeys_by_bit_field_id().get(&3)
+        );
+        assert_eq!(14, data_storage.journeys_by_bit_field_id()[&1].len());
+    }
+
+    #[test]
+    fn journey_by_sjyid() {
+        let data_storage = fixture_data_storage();
+
+        // The journeys 1 and 3 share the SJYID, the lowest journey ID wins.
+        assert_eq!(
+            Some("ch:1:sjyid:100001:1-001"),
+            data_storage.journeys().find(3).unwrap().sjyid(data_storage)
+        );
+        assert_eq!(
+            Some(1),
+            data_storage
+                .journey_by_sjyid("ch:1:sjyid:100001:1-001")
+                .map(|journey| journey.id())
+        );
+        assert!(
+            data_storage
+                .journey_by_sjyid("ch:1:sjyid:100001:1-002")
+                .is_none()
+        );
+        assert_eq!(1, data_storage.journeys_by_sjyid().len());
+    }
+
+    #[test]
+    fn journeys_crossing_border() {
+        let data_storage = fixture_data_storage();
+        let ids = |journeys: Vec<&Journey>| -> Vec<i32> {
+            journeys.into_iter().map(|journey| journey.id()).collect()
+        };
+
+        // Only Beta is crossed, Delta is always the last stop.
+        assert_eq!(
+            vec![1, 8, 9, 10, 13, 14],
+            ids(data_storage.journeys_crossing_border())
+        );
+        assert_eq!(
+            vec![1, 8, 9, 10, 13, 14],
+            ids(data_storage.journeys_crossing_border_at(8500002))
+        );
+        assert!(data_storage.journeys_crossing_border_at(8500004).is_empty());
+        // Alpha is not a border point.
+        assert!(data_storage.journeys_crossing_border_at(8500001).is_empty());
+        assert_eq!(1, data_storage.journeys_by_border_stop_id().len());
+    }
+
+    #[test]
+    fn platform_of() {
+        let data_storage = fixture_data_storage();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let names = |platforms: Vec<Option<&Platform>>| -> Vec<Option<String>> {
+            platforms
+                .into_iter()
+                .map(|platform| platform.map(|platform| platform.name().to_owned()))
+                .collect()
+        };
+
+        assert_eq!(
+            vec![Some("7".to_owned())],
+            names(data_storage.platform_of(1, 8500001, date))
+        );
+        // The journey 14 leaves Alpha at 14:00 and comes back at 14:20, each visit has its own platform.
+        assert_eq!(
+            vec![Some("7".to_owned()), Some("8".to_owned())],
+            names(data_storage.platform_of(14, 8500001, date))
+        );
+        assert_eq!(
+            vec![None],
+            names(data_storage.platform_of(14, 8500002, date))
+        );
+        assert!(data_storage.platform_of(14, 8500004, date).is_empty());
+        assert!(data_storage.platform_of(99, 8500001, date).is_empty());
+    }
+
+    #[test]
+    fn platform_at() {
+        let data_storage = fixture_data_storage();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let journey = data_storage.journeys().find(14).unwrap();
+        let name = |route_index| {
+            data_storage
+                .platform_at(journey, route_index, date)
+                .map(|platform| platform.name().to_owned())
+        };
+
+        // The GLEIS entries are matched by time: 14:00 at the route entry 0, 14:22 at the route entry 2.
+        assert_eq!(Some("7".to_owned()), name(0));
+        assert_eq!(None, name(1));
+        assert_eq!(Some("8".to_owned()), name(2));
+        assert_eq!(None, name(3));
+        assert_eq!(None, name(4));
+    }
+
+    #[test]
+    fn resolve_stop_ids() {
+        let data_storage = fixture_data_storage();
+
+        assert_eq!(vec![8500002], data_storage.resolve_stop_ids(8500002));
+        // The stop group 8500008 is not listed in BAHNHOF, the stop group 8500009 is.
+        assert_eq!(
+            vec![8500001, 8500004],
+            data_storage.resolve_stop_ids(8500008)
+        );
+        assert_eq!(
+            vec![8500003, 8500005, 8500009],
+            data_storage.resolve_stop_ids(8500009)
+        );
+        assert!(data_storage.resolve_stop_ids(8500099).is_empty());
+        assert_eq!(
+            Some(&vec![8500009]),
+            data_storage.stop_groups_by_stop_id().get(&8500005)
+        );
+    }
+}
@@ -0,0 +1,478 @@
+// A dated trip is a journey on a given service date, i.e. the date on which the journey leaves its first stop.
+// Only the sections of the route (*A VE) whose bit field is active on the service date are part of the trip.
+use std::collections::VecDeque;
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    models::{Journey, JourneyMetadataType, Model},
+    storage::DataStorage,
+};
+
+// ------------------------------------------------------------------------------------------------
+// --- DatedStopTime
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DatedStopTime {
+    route_index: usize,
+    stop_id: i32,
+    arrival_at: Option<NaiveDateTime>, // A None value means that the trip starts at this stop.
+    departure_at: Option<NaiveDateTime>, // A None value means that the trip ends at this stop.
+}
+
+impl DatedStopTime {
+    // Getters/Setters
+
+    /// The index of the stop in the route of the journey.
+    pub fn route_index(&self) -> usize {
+        self.route_index
+    }
+
+    pub fn stop_id(&self) -> i32 {
+        self.stop_id
+    }
+
+    pub fn arrival_at(&self) -> Option<NaiveDateTime> {
+        self.arrival_at
+    }
+
+    pub fn departure_at(&self) -> Option<NaiveDateTime> {
+        self.departure_at
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DatedTrip
+// ------------------------------------------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct DatedTrip<'a> {
+    journey: &'a Journey,
+    service_date: NaiveDate,
+    stop_times: Vec<DatedStopTime>,
+    continues_as: Vec<(&'a Journey, NaiveDate, usize)>,
+}
+
+impl<'a> DatedTrip<'a> {
+    // Getters/Setters
+
+    pub fn journey(&self) -> &'a Journey {
+        self.journey
+    }
+
+    /// The date on which the journey leaves its first stop.
+    pub fn service_date(&self) -> NaiveDate {
+        self.service_date
+    }
+
+    /// The stop times of the active sections, in route order. The stops between two active sections are skipped.
+    pub fn stop_times(&self) -> &Vec<DatedStopTime> {
+        &self.stop_times
+    }
+
+    /// The (journey, service date, route index of the stop where the journey is continued) of the trips
+    /// continuing this one (DURCHBI), i.e. the passengers can stay seated.
+    pub fn continues_as(&self) -> &Vec<(&'a Journey, NaiveDate, usize)> {
+        &self.continues_as
+    }
+
+    // Functions
+
+    pub fn departure_at(&self) -> NaiveDateTime {
+        // unwrap: A trip always starts with a departure.
+        self.stop_times.first().unwrap().departure_at.unwrap()
+    }
+
+    pub fn arrival_at(&self) -> NaiveDateTime {
+        // unwrap: A trip always ends with an arrival.
+        self.stop_times.last().unwrap().arrival_at.unwrap()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- DataStorage
+// ------------------------------------------------------------------------------------------------
+
+impl DataStorage {
+    /// Returns the trip of the journey on the service date, or None if the journey does not run on this date.
+    pub fn dated_trip<'a>(
+        &'a self,
+        journey: &'a Journey,
+        service_date: NaiveDate,
+    ) -> Option<DatedTrip<'a>> {
+        let is_hop_active = self.active_hops(journey, service_date);
+        let route_datetimes = journey.route_datetimes(service_date);
+        let stop_times: Vec<DatedStopTime> = (0..journey.route().len())
+            .filter_map(|i| dated_stop_time(journey, &route_datetimes, &is_hop_active, i))
+            .collect();
+
+        if stop_times.len() < 2 {
+            return None;
+        }
+
+        let continues_as = self
+            .through_service_continuations(journey, service_date, &route_datetimes)
+            .into_iter()
+            .filter(|&(_, _, _, last_route_index)| {
+                last_route_index > 0 && is_hop_active[last_route_index - 1]
+            })
+            .map(|(next_journey, next_service_date, _, last_route_index)| {
+                (next_journey, next_service_date, last_route_index)
+            })
+            .collect();
+
+        Some(DatedTrip {
+            journey,
+            service_date,
+            stop_times,
+            continues_as,
+        })
+    }
+
+    /// Returns, for each route entry i but the last one, whether the journey runs from the route entry i
+    /// to the route entry i + 1 on the service date, i.e. whether an active section (*A VE) contains both.
+    pub(crate) fn active_hops(&self, journey: &Journey, service_date: NaiveDate) -> Vec<bool> {
+        let mut is_hop_active = vec![false; journey.route().len().saturating_sub(1)];
+
+        for entry in journey
+            .metadata()
+            .get(&JourneyMetadataType::BitField)
+            .into_iter()
+            .flatten()
+            .filter(|entry| self.is_bit_field_active(entry.bit_field_id(), service_date))
+        {
+            if let Some((start, end)) = journey.section_of(entry) {
+                is_hop_active[start..end].fill(true);
+            }
+        }
+
+        is_hop_active
+    }
+
+    /// Returns the trips leaving their first stop on the date, sorted by journey ID.
+    pub fn trips_on(&self, date: NaiveDate) -> impl Iterator<Item = DatedTrip<'_>> {
+        self.trips_between(date, date)
+    }
+
+    /// Returns the trips leaving their first stop between the two dates (inclusive), sorted by service date, then by journey ID.
+    pub fn trips_between(
+        &self,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> impl Iterator<Item = DatedTrip<'_>> {
+        start_date
+            .iter_days()
+            .take_while(move |&date| date <= end_date)
+            .flat_map(move |date| {
+                self.journeys_running_on(date)
+                    .into_iter()
+                    .filter_map(move |journey| self.dated_trip(journey, date))
+            })
+    }
+
+    /// Returns the (journey, service date) of the trips continuing the trip of the journey on the service date (DURCHBI),
+    /// sorted by journey ID. There are several continuations if the train is split.
+    pub fn continuation_of<'a>(
+        &'a self,
+        journey: &'a Journey,
+        service_date: NaiveDate,
+    ) -> Vec<(&'a Journey, NaiveDate)> {
+        let Some(trip) = self.dated_trip(journey, service_date) else {
+            return Vec::new();
+        };
+
+        let mut continuations: Vec<(&Journey, NaiveDate)> = trip
+            .continues_as()
+            .iter()
+            .map(|&(next_journey, next_service_date, _)| (next_journey, next_service_date))
+            .collect();
+        continuations.sort_by_key(|(next_journey, _)| next_journey.id());
+        continuations.dedup_by_key(|(next_journey, _)| next_journey.id());
+        continuations
+    }
+
+    /// Returns the (journey, service date) of the trips continued by the trip of the journey on the service date (DURCHBI),
+    /// sorted by journey ID. There are several of them if the train is the result of a merge.
+    pub fn continued_from<'a>(
+        &'a self,
+        journey: &'a Journey,
+        service_date: NaiveDate,
+    ) -> Vec<(&'a Journey, NaiveDate)> {
+        let Some(through_service_ids) = self
+            .through_services_by_next_journey_id()
+            .get(&journey.journey_id())
+        else {
+            return Vec::new();
+        };
+
+        let mut previous_trips: Vec<(&Journey, NaiveDate)> = through_service_ids
+            .iter()
+            .filter_map(|&id| self.through_service().find(id))
+            .filter_map(|through_service| {
+                self.journeys_by_journey_id()
+                    .get(through_service.journey_1_id())
+                    .and_then(|&id| self.journeys().find(id))
+            })
+            .flat_map(|previous_journey| {
+                // The continued journey left its first stop at most max_trip_days days before.
+                (0..=self.max_trip_days())
+                    .map(move |days| (previous_journey, service_date - Days::new(days as u64)))
+            })
+            .filter(|&(previous_journey, date)| {
+                self.continuation_of(previous_journey, date).iter().any(
+                    |&(next_journey, next_service_date)| {
+                        next_journey.id() == journey.id() && next_service_date == service_date
+                    },
+                )
+            })
+            .collect();
+        previous_trips.sort_by_key(|(previous_journey, date)| (previous_journey.id(), *date));
+        previous_trips.dedup_by_key(|(previous_journey, date)| (previous_journey.id(), *date));
+        previous_trips
+    }
+
+    /// Returns the (journey, service date) of the trips of the vehicle run containing the trip of the journey
+    /// on the service date, in order, i.e. the chain of trips on which the passengers can stay seated.
+    /// The chain stops where the train is split or merged, as the vehicle run is then ambiguous.
+    pub fn vehicle_run<'a>(
+        &'a self,
+        journey: &'a Journey,
+        service_date: NaiveDate,
+    ) -> Vec<(&'a Journey, NaiveDate)> {
+        let mut run = VecDeque::from([(journey, service_date)]);
+        // Protects against the through services forming a loop.
+        let mut visited = FxHashSet::from_iter([(journey.id(), service_date)]);
+
+        let mut current = (journey, service_date);
+        while let [previous] = self.continued_from(current.0, current.1)[..] {
+            if !visited.insert((previous.0.id(), previous.1)) {
+                break;
+            }
+            run.push_front(previous);
+            current = previous;
+        }
+
+        let mut current = (journey, service_date);
+        while let [next] = self.continuation_of(current.0, current.1)[..] {
+            if !visited.insert((next.0.id(), next.1)) {
+                break;
+            }
+            run.push_back(next);
+            current = next;
+        }
+
+        run.into()
+    }
+
+    /// Returns the journeys having at least one section active on the date, sorted by ID.
+    fn journeys_running_on(&self, date: NaiveDate) -> Vec<&Journey> {
+        let Some(active_bit_field_ids) = self.bit_fields_by_day().get(&date) else {
+            return Vec::new();
+        };
+
+        let mut journey_ids: Vec<i32> = active_bit_field_ids
+            .iter()
+            .filter_map(|bit_field_id| self.journeys_by_bit_field_id().get(bit_field_id))
+            .flatten()
+            .copied()
+            .collect();
+        journey_ids.sort();
+        journey_ids.dedup();
+
+        journey_ids
+            .into_iter()
+            .filter_map(|journey_id| self.journeys().find(journey_id))
+            .collect()
+    }
+
+    /// Returns the (journey, service date, route index, last route index of the continued journey) of the journeys
+    /// continuing the given journey (DURCHBI).
+    pub(crate) fn through_service_continuations(
+        &self,
+        journey: &Journey,
+        service_date: NaiveDate,
+        route_datetimes: &[(Option<NaiveDateTime>, Option<NaiveDateTime>)],
+    ) -> Vec<(&Journey, NaiveDate, usize, usize)> {
+        let Some(through_service_ids) = self
+            .through_services_by_journey_id()
+            .get(&journey.journey_id())
+        else {
+            return Vec::new();
+        };
+
+        through_service_ids
+            .iter()
+            .filter_map(|&id| self.through_service().find(id))
+            .filter(|through_service| {
+                self.is_bit_field_active(Some(through_service.bit_field_id()), service_date)
+            })
+            .filter_map(|through_service| {
+                let last_route_index = journey.route().iter().rposition(|route_entry| {
+                    route_entry.stop_id() == through_service.journey_1_stop_id()
+                })?;
+                let arrival_at = route_datetimes[last_route_index].0?;
+
+                let next_journey = self
+                    .journeys_by_journey_id()
+                    .get(through_service.journey_2_id())
+                    .and_then(|&id| self.journeys().find(id))?;
+                let next_route_index = next_journey.route().iter().position(|route_entry| {
+                    route_entry.stop_id() == through_service.journey_2_stop_id()
+                })?;
+
+                // The continuing journey may have left its first stop up to max_trip_days days before the arrival.
+                (0..=self.max_trip_days())
+                    .map(|days| arrival_at.date() - Days::new(days as u64))
+                    .filter(|&date| self.is_bit_field_active(next_journey.bit_field_id(), date))
+                    .filter_map(|date| {
+                        next_journey.route_datetimes(date)[next_route_index]
+                            .1
+                            .filter(|departure_at| *departure_at >= arrival_at)
+                            .map(|departure_at| (departure_at, date))
+                    })
+                    .min()
+                    .map(|(_, date)| (next_journey, date, next_route_index, last_route_index))
+            })
+            .collect()
+    }
+}
+
+// ------------------------------------------------------------------------------------------------
+// --- Helper Functions
+// ------------------------------------------------------------------------------------------------
+
+/// Returns the stop time of the route entry, or None if the journey neither arrives at nor departs from it
+/// (see DataStorage::active_hops).
+pub(crate) fn dated_stop_time(
+    journey: &Journey,
+    route_datetimes: &[(Option<NaiveDateTime>, Option<NaiveDateTime>)],
+    is_hop_active: &[bool],
+    route_index: usize,
+) -> Option<DatedStopTime> {
+    let arrives = route_index > 0 && is_hop_active[route_index - 1];
+    let departs = route_index < is_hop_active.len() && is_hop_active[route_index];
+
+    (arrives || departs).then(|| DatedStopTime {
+        route_index,
+        stop_id: journey.route()[route_index].stop_id(),
+        arrival_at: route_datetimes[route_index].0.filter(|_| arrives),
+        departure_at: route_datetimes[route_index].1.filter(|_| departs),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::tests::fixture_data_storage;
+    use pretty_assertions::assert_eq;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn datetime(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn trips_on() {
+        let data_storage = fixture_data_storage();
+        let trips: Vec<DatedTrip> = data_storage.trips_on(date("2025-03-03")).collect();
+
+        assert_eq!(
+            (1..=14).collect::<Vec<_>>(),
+            trips
+                .iter()
+                .map(|trip| trip.journey().id())
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            trips
+                .iter()
+                .all(|trip| trip.service_date() == date("2025-03-03"))
+        );
+
+        // The journey 5 crosses midnight, it keeps the date on which it leaves its first stop.
+        let trip = &trips[4];
+        assert_eq!(datetime("2025-03-03 23:50"), trip.departure_at());
+        assert_eq!(datetime("2025-03-04 00:20"), trip.arrival_at());
+    }
+
+    #[test]
+    fn trips_on_skips_inactive_sections() {
+        let data_storage = fixture_data_storage();
+        let route_indexes = |date| {
+            data_storage
+                .trips_on(date)
+                .find(|trip| trip.journey().id() == 8)
+                .unwrap()
+                .stop_times()
+                .iter()
+                .map(|stop_time| stop_time.route_index())
+                .collect::<Vec<_>>()
+        };
+
+        // The section from Gamma to Delta only runs on the first two days of the timetable.
+        assert_eq!(vec![0, 1, 2, 3], route_indexes(date("2025-01-01")));
+        assert_eq!(vec![0, 1], route_indexes(date("2025-03-03")));
+    }
+
+    #[test]
+    fn trips_between() {
+        let data_storage = fixture_data_storage();
+        let trips: Vec<DatedTrip> = data_storage
+            .trips_between(date("2025-03-03"), date("2025-03-04"))
+            .collect();
+
+        assert_eq!(28, trips.len());
+        assert_eq!(
+            (date("2025-03-03"), 1),
+            (trips[0].service_date(), trips[0].journey().id())
+        );
+        assert_eq!(
+            (date("2025-03-04"), 1),
+            (trips[14].service_date(), trips[14].journey().id())
+        );
+        assert_eq!(datetime("2025-03-05 00:20"), trips[14 + 4].arrival_at());
+    }
+
+    #[test]
+    fn through_services() {
+        let data_storage = fixture_data_storage();
+        let journey = |id| data_storage.journeys().find(id).unwrap();
+        let ids = |trips: Vec<(&Journey, NaiveDate)>| {
+            trips
+                .into_iter()
+                .map(|(journey, date)| (journey.id(), date))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![(7, date("2025-03-03"))],
+            ids(data_storage.continuation_of(journey(6), date("2025-03-03")))
+        );
+        assert_eq!(
+            vec![(6, date("2025-03-03"))],
+            ids(data_storage.continued_from(journey(7), date("2025-03-03")))
+        );
+        assert!(
+            data_storage
+                .continued_from(journey(6), date("2025-03-03"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn trips_outside_of_the_timetable_period() {
+        let data_storage = fixture_data_storage();
+        assert_eq!(0, data_storage.trips_on(date("2024-12-31")).count());
+        assert_eq!(
+            14,
+            data_storage
+                .trips_between(date("2025-12-31"), date("2026-01-01"))
+                .count()
+        );
+    }
+}